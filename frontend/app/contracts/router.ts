@@ -114,5 +114,62 @@ export const ROUTER_ABI = [
     ],
     "outputs": [],
     "stateMutability": "nonpayable"
+  },
+  {
+    "type": "function",
+    "name": "executeWithAdapter",
+    "inputs": [
+      {
+        "name": "adapter",
+        "type": "address"
+      },
+      {
+        "name": "toChain",
+        "type": "uint256"
+      },
+      {
+        "name": "token",
+        "type": "address"
+      },
+      {
+        "name": "amount",
+        "type": "uint256"
+      },
+      {
+        "name": "recipient",
+        "type": "address"
+      },
+      {
+        "name": "minAmountOut",
+        "type": "uint256"
+      },
+      {
+        "name": "deadline",
+        "type": "uint256"
+      },
+      {
+        "name": "nativeDropAmount",
+        "type": "uint256"
+      },
+      {
+        "name": "payload",
+        "type": "bytes"
+      },
+      {
+        "name": "integrator",
+        "type": "address"
+      },
+      {
+        "name": "integratorFeeBps",
+        "type": "uint256"
+      }
+    ],
+    "outputs": [
+      {
+        "name": "",
+        "type": "bytes32"
+      }
+    ],
+    "stateMutability": "payable"
   }
 ];
\ No newline at end of file
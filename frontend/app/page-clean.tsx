@@ -19,7 +19,7 @@ import {
 } from './config';
 import { ROUTER_ABI } from './contracts/router';
 
-// Use canonical ROUTER_ABI from contracts (findBestRoute, executeTransfer)
+// Use canonical ROUTER_ABI from contracts (findBestRoute, executeTransfer, executeWithAdapter)
 
 // ERC20 ABI (minimal)
 const ERC20_ABI = [
@@ -48,6 +48,7 @@ export default function Page() {
   const [routes, setRoutes] = useState<any[]>([]);
   const [selectedRoute, setSelectedRoute] = useState<number | null>(null);
   const [optimizationValue, setOptimizationValue] = useState(0); // 0=cheapest, 100=fastest
+  const [slippageBps, setSlippageBps] = useState(50); // accepted shortfall from the quote, 0.5%
   const [loadingRoutes, setLoadingRoutes] = useState(false);
   const [txStatus, setTxStatus] = useState<TransactionStatus>('idle');
   const [txHash, setTxHash] = useState<string>();
@@ -105,12 +106,14 @@ export default function Page() {
 
     const route = routesData as any;
     const formattedRoutes = [{
+      bridgeAdapter: route.bridgeAdapter as `0x${string}`,
       bridgeName: route.bridgeName,
       estimatedTime: Number(route.estimatedTime),
       estimatedGasCost: Number(formatUnits(route.estimatedGasCost, 8)),
       bridgeFee: Number(formatUnits(route.bridgeFee, tokenDecimals)),
       totalCostUSD: Number(formatUnits(route.totalCostUSD, 8)),
       amountOut: Number(formatUnits(route.amountOut, tokenDecimals)),
+      amountOutWei: route.amountOut.toString(), // exact quote; the float above is display-only
       available: route.available,
     }];
 
@@ -196,6 +199,11 @@ export default function Page() {
       setTxError(undefined);
 
       const amountInWei = parseUnits(amount, tokenDecimals);
+      // Revert on-chain if the picked route delivers less than its quote minus the
+      // tolerance, or the tx sits for 20 minutes
+      const route = routes[selectedRoute];
+      const minAmountOut = (BigInt(route.amountOutWei) * BigInt(10_000 - slippageBps)) / 10_000n;
+      const deadline = BigInt(Math.floor(Date.now() / 1000) + 20 * 60);
       
       await approve({
         address: tokenAddress,
@@ -224,12 +232,19 @@ export default function Page() {
         await bridge({
           address: routerAddress,
           abi: ROUTER_ABI,
-          functionName: 'executeBestRoute' as any,
+          functionName: 'executeWithAdapter' as any,
           args: [
+            route.bridgeAdapter,
             BigInt(toChainId),
             tokenAddress,
             amountInWei,
             address,
+            minAmountOut,
+            deadline,
+            0n, // no native gas drop-off
+            '0x', // no destination payload
+            '0x0000000000000000000000000000000000000000', // no integrator
+            0n, // no integrator fee
           ] as any,
         });
       }
@@ -358,6 +373,21 @@ export default function Page() {
                   </div>
                 </div>
 
+                <div>
+                  <label className="block text-sm font-medium text-gray-700 mb-2">
+                    Slippage Tolerance (%)
+                  </label>
+                  <input
+                    type="number"
+                    value={slippageBps / 100}
+                    onChange={(e) => setSlippageBps(Math.min(Math.max(Math.round(Number(e.target.value) * 100), 0), 5_000))}
+                    className="w-full p-3 bg-white border-2 border-gray-200 rounded-xl focus:border-purple-500 outline-none transition-colors"
+                    step="0.1"
+                    min="0"
+                    max="50"
+                  />
+                </div>
+
                 <button
                   onClick={handleBridge}
                   disabled={
@@ -0,0 +1,157 @@
+//! Role-based access control shared by the contracts in the crate.
+//!
+//! A role id is the `keccak256` of its name. Holders of `ADMIN_ROLE` grant and
+//! revoke every role, and each role's members can be listed. Contracts embed
+//! `AccessControl` like `Ownable` and gate methods with `only_role`.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{b256, Address, B256, U256},
+    prelude::*,
+};
+
+use crate::errors::{InvalidAddress, MissingRole, SettlementError};
+use crate::events::{RoleGranted, RoleRevoked};
+
+/// keccak256("ADMIN"): grants and revokes roles
+pub const ADMIN_ROLE: B256 =
+    b256!("df8b4c520ffe197c5343c6f5aec59570151ef9a492f2c624fd45ddde6135ec42");
+/// keccak256("ADAPTER_MANAGER"): adds and removes bridge adapters
+pub const ADAPTER_MANAGER_ROLE: B256 =
+    b256!("ebb0d4113feb7ebd3e280540df9863963765c1e05ca9bb117f5cd561e6d4ec70");
+/// keccak256("ORACLE_UPDATER"): pushes gas prices to the price oracle
+pub const ORACLE_UPDATER_ROLE: B256 =
+    b256!("44b95bb537aef5ad632cef9503788828b27f44285a867eeb813406ad80ee3748");
+/// keccak256("PAUSER"): pauses routing
+pub const PAUSER_ROLE: B256 =
+    b256!("539440820030c4994db4e31b6b800deafd503688728f932addfe7a410515c14c");
+/// keccak256("FEE_MANAGER"): sets protocol and integrator fees and withdraws fees
+pub const FEE_MANAGER_ROLE: B256 =
+    b256!("8e24cef3d118e2320bff2d4ac4e82fd5ff0dd3a6e6044ca19f91cd3b6f84750e");
+/// keccak256("RELAYER"): reports whether dispatched transfers completed or failed
+pub const RELAYER_ROLE: B256 =
+    b256!("ab4f864e5201b0fde9b5ee3e4cf96384802b0ffdfcf7f9de4699ce21a30afc4f");
+
+sol_storage! {
+    pub struct AccessControl {
+        mapping(bytes32 => mapping(address => bool)) members;
+        // Members of each role, and each member's position in the list plus one
+        mapping(bytes32 => address[]) member_list;
+        mapping(bytes32 => mapping(address => uint256)) member_index;
+    }
+}
+
+#[public]
+impl AccessControl {
+    /// Check whether `account` holds `role`
+    #[allow(non_snake_case)]
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.members.getter(role).get(account)
+    }
+
+    /// Get every account holding `role`, in no particular order
+    #[allow(non_snake_case)]
+    pub fn get_role_members(&self, role: B256) -> Vec<Address> {
+        let list = self.member_list.getter(role);
+        (0..list.len()).filter_map(|i| list.get(i)).collect()
+    }
+
+    /// Get how many accounts hold `role`
+    #[allow(non_snake_case)]
+    pub fn get_role_member_count(&self, role: B256) -> U256 {
+        U256::from(self.member_list.getter(role).len())
+    }
+
+    /// Grant `role` to `account`. Only admins can grant.
+    #[allow(non_snake_case)]
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), SettlementError> {
+        self.only_role(ADMIN_ROLE)?;
+        if account == Address::ZERO {
+            return Err(InvalidAddress {}.into());
+        }
+        self.grant(role, account);
+        Ok(())
+    }
+
+    /// Revoke `role` from `account`. Only admins can revoke.
+    #[allow(non_snake_case)]
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), SettlementError> {
+        self.only_role(ADMIN_ROLE)?;
+        self.revoke(role, account);
+        Ok(())
+    }
+
+    /// Give up `role` held by the caller
+    #[allow(non_snake_case)]
+    pub fn renounce_role(&mut self, role: B256) -> Result<(), SettlementError> {
+        let caller = self.vm().msg_sender();
+        self.revoke(role, caller);
+        Ok(())
+    }
+}
+
+impl AccessControl {
+    /// Make `admin` the first admin
+    pub fn init(&mut self, admin: Address) {
+        self.grant(ADMIN_ROLE, admin);
+    }
+
+    /// Move `ADMIN_ROLE` with ownership, from the previous owner `from` to the new
+    /// owner `to`. Zero on either side is skipped.
+    pub fn transfer_admin(&mut self, from: Address, to: Address) {
+        if from != Address::ZERO {
+            self.revoke(ADMIN_ROLE, from);
+        }
+        if to != Address::ZERO {
+            self.grant(ADMIN_ROLE, to);
+        }
+    }
+
+    pub fn only_role(&self, role: B256) -> Result<(), SettlementError> {
+        let account = self.vm().msg_sender();
+        if !self.has_role(role, account) {
+            return Err(MissingRole { role, account }.into());
+        }
+        Ok(())
+    }
+
+    fn grant(&mut self, role: B256, account: Address) {
+        if self.has_role(role, account) {
+            return;
+        }
+
+        self.members.setter(role).insert(account, true);
+        let mut list = self.member_list.setter(role);
+        list.push(account);
+        let position = U256::from(list.len());
+        self.member_index.setter(role).insert(account, position);
+
+        log(self.vm(), RoleGranted {
+            role,
+            account,
+            sender: self.vm().msg_sender(),
+        });
+    }
+
+    fn revoke(&mut self, role: B256, account: Address) {
+        if !self.has_role(role, account) {
+            return;
+        }
+
+        // Move the last member into the revoked one's slot
+        let index = self.member_index.getter(role).get(account).to::<usize>() - 1;
+        let mut list = self.member_list.setter(role);
+        let last = list.get(list.len() - 1).unwrap();
+        list.setter(index).unwrap().set(last);
+        list.pop();
+        self.member_index.setter(role).insert(last, U256::from(index + 1));
+        self.member_index.setter(role).insert(account, U256::ZERO);
+        self.members.setter(role).insert(account, false);
+
+        log(self.vm(), RoleRevoked {
+            role,
+            account,
+            sender: self.vm().msg_sender(),
+        });
+    }
+}
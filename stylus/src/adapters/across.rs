@@ -1,8 +1,20 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloc::{string::String, vec::Vec};
-use stylus_sdk::{evm, msg, prelude::*, storage::StorageMap};
+use stylus_sdk::{call::Call, evm, msg, prelude::*, storage::StorageMap};
 
-use crate::adapters::{BridgeRoute, IBridgeAdapter};
+use crate::adapters::{ensure_deadline, ensure_native_value, BridgeRoute, IBridgeAdapter};
+use crate::erc20::ERC20Helper;
+use crate::errors::{
+    AmountBelowMinimum, BridgeNotConfigured, FeeTooHigh, InsufficientOutput, InvalidAddress,
+    InvalidToken, SettlementError, TokenNotSupported,
+};
+use crate::ownable::Ownable;
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
+use crate::settlement::encode_message;
+use crate::NATIVE_TOKEN;
+
+/// `relayerFeePct` is 1e18 for 100%, so one basis point is 1e14
+const FEE_PCT_PER_BPS: u64 = 100_000_000_000_000;
 
 /// Across Protocol Spoke Pool Interface
 sol_interface! {
@@ -13,55 +25,125 @@ sol_interface! {
             uint256 amount,
             uint256 destinationChainId,
             uint64 relayerFeePct,
-            uint32 quoteTimestamp
+            uint32 quoteTimestamp,
+            bytes message
         ) external payable;
     }
 }
 
 #[storage]
 pub struct AcrossBridgeAdapter {
-    owner: Address,
+    #[borrow]
+    ownable: Ownable,
     // Spoke pool contract address (single pool handles all tokens)
     spoke_pool: Address,
     supported_tokens: StorageMap<Address, bool>,
     min_amounts: StorageMap<Address, U256>,
     // Relayer fee percentage in basis points (e.g., 15 = 0.15%)
     relayer_fee_bps: U256,
+    // Held for the duration of every state-mutating call
+    reentrancy_guard: ReentrancyGuard,
+    // WETH, the origin token of native deposits
+    wrapped_native: Address,
+    // `SettlementReceiver` per destination chain id
+    receivers: StorageMap<U256, Address>,
+}
+
+impl NonReentrant for AcrossBridgeAdapter {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
 }
 
 impl AcrossBridgeAdapter {
-    pub fn new(spoke_pool: Address) -> Self {
+    pub fn new(spoke_pool: Address, wrapped_native: Address) -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
+        instance.ownable.init(msg::sender())?;
         instance.spoke_pool = spoke_pool;
+        instance.wrapped_native = wrapped_native;
         instance.relayer_fee_bps = U256::from(15); // 0.15% default
-        instance
+        Ok(instance)
     }
 
     pub fn add_supported_token(
         &mut self,
         token: Address,
         min_amount: U256,
-    ) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(token != Address::ZERO, "Invalid token");
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
 
-        self.supported_tokens.insert(token, true);
-        self.min_amounts.insert(token, min_amount);
-        Ok(())
+            this.supported_tokens.insert(token, true);
+            this.min_amounts.insert(token, min_amount);
+            Ok(())
+        })
     }
 
-    pub fn set_spoke_pool(&mut self, spoke_pool: Address) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(spoke_pool != Address::ZERO, "Invalid spoke pool");
-        self.spoke_pool = spoke_pool;
-        Ok(())
+    pub fn set_spoke_pool(&mut self, spoke_pool: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(spoke_pool != Address::ZERO, InvalidAddress {});
+            this.spoke_pool = spoke_pool;
+            Ok(())
+        })
+    }
+
+    pub fn set_relayer_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(
+                fee_bps <= U256::from(1000), // Max 10%
+                FeeTooHigh { feeBps: fee_bps, maxFeeBps: U256::from(1000) }
+            );
+            this.relayer_fee_bps = fee_bps;
+            Ok(())
+        })
     }
 
-    pub fn set_relayer_fee(&mut self, fee_bps: U256) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(fee_bps <= U256::from(1000), "Fee too high"); // Max 10%
-        self.relayer_fee_bps = fee_bps;
+    /// Deliver transfers to `chain_id` to the `SettlementReceiver` there
+    pub fn set_receiver(
+        &mut self,
+        chain_id: U256,
+        receiver: Address,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(receiver != Address::ZERO, InvalidAddress {});
+            this.receivers.insert(chain_id, receiver);
+            Ok(())
+        })
+    }
+
+    /// Deposit `amount` of `token` to `recipient` on `to_chain`, sending native
+    /// deposits as WETH with the ETH as value
+    fn deposit(
+        &self,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        to_chain: U256,
+        message: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        let (origin_token, value) = if token == NATIVE_TOKEN {
+            (self.wrapped_native, amount)
+        } else {
+            ERC20Helper::approve_token(token, self.spoke_pool, amount)?;
+            (token, U256::ZERO)
+        };
+        let relayer_fee_pct = (self.relayer_fee_bps * U256::from(FEE_PCT_PER_BPS)).to::<u64>();
+        let quote_timestamp = evm::block_timestamp() as u32;
+
+        ISpokePool::new(self.spoke_pool).deposit(
+            Call::new().value(value),
+            recipient,
+            origin_token,
+            amount,
+            to_chain,
+            relayer_fee_pct,
+            quote_timestamp,
+            message.into(),
+        )?;
         Ok(())
     }
 
@@ -73,24 +155,41 @@ impl AcrossBridgeAdapter {
 }
 
 impl IBridgeAdapter for AcrossBridgeAdapter {
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         Ok(("Across Protocol".to_string(), true))
     }
 
+    fn supports_native(&self) -> bool {
+        true
+    }
+
+    // Relayers fill the drop as native gas to the recipient
+    fn supports_native_drop(&self) -> bool {
+        true
+    }
+
+    // The payload rides in the deposit message to the receiver
+    fn supports_payload(&self) -> bool {
+        true
+    }
+
     fn get_route(
         &self,
         _from_chain: U256,
         _to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>> {
+    ) -> Result<BridgeRoute, SettlementError> {
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         let min_amount = self.min_amounts.get(&token).unwrap_or(&U256::ZERO);
-        ensure!(amount >= *min_amount, "Amount below minimum");
+        ensure!(
+            amount >= *min_amount,
+            AmountBelowMinimum { amount, minimum: *min_amount }
+        );
 
         // Across fees: relayer fee (0.15%)
         let relayer_fee = self.calculate_relayer_fee(amount);
@@ -106,30 +205,47 @@ impl IBridgeAdapter for AcrossBridgeAdapter {
 
     fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
-        _data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        data: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        ensure_deadline(deadline)?;
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
-        ensure!(self.spoke_pool != Address::ZERO, "Spoke pool not set");
+        ensure!(self.spoke_pool != Address::ZERO, BridgeNotConfigured { token });
+        let receiver = *self.receivers.get(&to_chain).unwrap_or(&Address::ZERO);
+        ensure!(receiver != Address::ZERO, BridgeNotConfigured { token });
+        // Native deposits pass WETH as originToken with the ETH as msg.value;
+        // the spoke pool wraps it
+        ensure_native_value(token, amount, native_drop_amount)?;
 
-        let relayer_fee_pct = self.relayer_fee_bps;
+        // Relayers fill `amount` minus their fee, so that is what the recipient gets
+        let amount_out = amount.saturating_sub(self.calculate_relayer_fee(amount));
+        ensure!(
+            amount_out >= min_amount_out,
+            InsufficientOutput { amountOut: amount_out, minAmountOut: min_amount_out }
+        );
         
-        // Use current block timestamp as quote timestamp
-        let quote_timestamp = U256::from(evm::block_timestamp());
-
-        // Create spoke pool instance
-        let spoke_pool = ISpokePool::new(self.spoke_pool);
-
-        // Approve token spend if needed (would need ERC20 interface)
+        // Handed to the destination receiver's handleV3AcrossMessage with the fill
+        let message = encode_message(transfer_id, recipient, amount, &data);
+        self.deposit(token, amount, receiver, to_chain, message)?;
 
-        // Execute the deposit (bridge transfer) - simplified for now
-        // In production, would call spoke_pool.deposit with proper parameters
+        // The drop is a second, message-less deposit of the value paid for it,
+        // straight to the recipient, which relayers fill in native gas. Paying
+        // it from the receiver instead would let any Across message claim it.
+        if native_drop_amount > U256::ZERO {
+            let bridged_value = if token == NATIVE_TOKEN { amount } else { U256::ZERO };
+            let drop_value = msg::value() - bridged_value;
+            self.deposit(NATIVE_TOKEN, drop_value, recipient, to_chain, Vec::new())?;
+        }
 
         Ok(())
     }
@@ -138,30 +254,59 @@ impl IBridgeAdapter for AcrossBridgeAdapter {
 // Public external interface for router integration
 #[public]
 impl AcrossBridgeAdapter {
-    pub fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    pub fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         <Self as IBridgeAdapter>::get_bridge_info(self)
     }
 
+    pub fn supports_native(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native(self)
+    }
+
+    pub fn supports_native_drop(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native_drop(self)
+    }
+
+    pub fn supports_payload(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_payload(self)
+    }
+
     pub fn get_route(
         &self,
         from_chain: U256,
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<(String, U256, U256, U256, bool), Vec<u8>> {
+    ) -> Result<(String, U256, U256, U256, bool), SettlementError> {
         let r = <Self as IBridgeAdapter>::get_route(self, from_chain, to_chain, token, amount)?;
         Ok((r.bridge_name, r.estimated_time, r.estimated_gas, r.fee, r.available))
     }
 
+    #[payable]
     pub fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
         data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
-        let _ = data; // unused
-        <Self as IBridgeAdapter>::bridge_tokens(self, to_chain, token, amount, recipient, Vec::new())
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            <Self as IBridgeAdapter>::bridge_tokens(
+                this,
+                transfer_id,
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop_amount,
+                data,
+            )
+        })
     }
 }
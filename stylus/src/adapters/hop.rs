@@ -1,8 +1,14 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloc::{string::String, vec::Vec};
-use stylus_sdk::{evm, msg, prelude::*, storage::StorageMap};
+use stylus_sdk::{contract, msg, prelude::*, storage::StorageMap};
 
-use crate::adapters::{BridgeRoute, IBridgeAdapter};
+use crate::adapters::{ensure_deadline, BridgeRoute, IBridgeAdapter};
+use crate::errors::{
+    AmountBelowMinimum, BridgeNotConfigured, FeeTooHigh, InvalidAddress, InvalidToken,
+    NativeDropNotSupported, PayloadNotSupported, SettlementError, TokenNotSupported,
+};
+use crate::ownable::Ownable;
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
 
 /// Hop Protocol Bridge Interface
 sol_interface! {
@@ -37,7 +43,8 @@ sol_interface! {
 
 #[storage]
 pub struct HopBridgeAdapter {
-    owner: Address,
+    #[borrow]
+    ownable: Ownable,
     // Hop bridge contract per token
     hop_bridges: StorageMap<Address, Address>,
     // Hop AMM contract per token
@@ -46,14 +53,22 @@ pub struct HopBridgeAdapter {
     min_amounts: StorageMap<Address, U256>,
     // Bonder fee in basis points (e.g., 10 = 0.1%)
     bonder_fee_bps: U256,
+    // Held for the duration of every state-mutating call
+    reentrancy_guard: ReentrancyGuard,
+}
+
+impl NonReentrant for HopBridgeAdapter {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
 }
 
 impl HopBridgeAdapter {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
+        instance.ownable.init(msg::sender())?;
         instance.bonder_fee_bps = U256::from(10); // 0.1% default
-        instance
+        Ok(instance)
     }
 
     pub fn add_supported_token(
@@ -62,38 +77,45 @@ impl HopBridgeAdapter {
         hop_bridge: Address,
         hop_amm: Address,
         min_amount: U256,
-    ) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(token != Address::ZERO, "Invalid token");
-        ensure!(hop_bridge != Address::ZERO, "Invalid bridge");
-        ensure!(hop_amm != Address::ZERO, "Invalid AMM");
-
-        self.supported_tokens.insert(token, true);
-        self.hop_bridges.insert(token, hop_bridge);
-        self.hop_amms.insert(token, hop_amm);
-        self.min_amounts.insert(token, min_amount);
-        Ok(())
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
+            ensure!(hop_bridge != Address::ZERO, InvalidAddress {});
+            ensure!(hop_amm != Address::ZERO, InvalidAddress {});
+
+            this.supported_tokens.insert(token, true);
+            this.hop_bridges.insert(token, hop_bridge);
+            this.hop_amms.insert(token, hop_amm);
+            this.min_amounts.insert(token, min_amount);
+            Ok(())
+        })
     }
 
-    pub fn set_bonder_fee(&mut self, fee_bps: U256) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(fee_bps <= U256::from(1000), "Fee too high"); // Max 10%
-        self.bonder_fee_bps = fee_bps;
-        Ok(())
+    pub fn set_bonder_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(
+                fee_bps <= U256::from(1000), // Max 10%
+                FeeTooHigh { feeBps: fee_bps, maxFeeBps: U256::from(1000) }
+            );
+            this.bonder_fee_bps = fee_bps;
+            Ok(())
+        })
     }
 
-    fn get_hop_bridge(&self, token: Address) -> Result<Address, Vec<u8>> {
+    fn get_hop_bridge(&self, token: Address) -> Result<Address, SettlementError> {
         self.hop_bridges
             .get(&token)
             .copied()
-            .ok_or_else(|| "Hop bridge not set".as_bytes().to_vec())
+            .ok_or_else(|| BridgeNotConfigured { token }.into())
     }
 
-    fn get_hop_amm(&self, token: Address) -> Result<Address, Vec<u8>> {
+    fn get_hop_amm(&self, token: Address) -> Result<Address, SettlementError> {
         self.hop_amms
             .get(&token)
             .copied()
-            .ok_or_else(|| "Hop AMM not set".as_bytes().to_vec())
+            .ok_or_else(|| BridgeNotConfigured { token }.into())
     }
 
     fn calculate_bonder_fee(&self, amount: U256) -> U256 {
@@ -104,24 +126,39 @@ impl HopBridgeAdapter {
 }
 
 impl IBridgeAdapter for HopBridgeAdapter {
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         Ok(("Hop Protocol".to_string(), true))
     }
 
+    fn supports_native(&self) -> bool {
+        false
+    }
+
+    fn supports_native_drop(&self) -> bool {
+        false
+    }
+
+    fn supports_payload(&self) -> bool {
+        false
+    }
+
     fn get_route(
         &self,
         _from_chain: U256,
         _to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>> {
+    ) -> Result<BridgeRoute, SettlementError> {
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         let min_amount = self.min_amounts.get(&token).unwrap_or(&U256::ZERO);
-        ensure!(amount >= *min_amount, "Amount below minimum");
+        ensure!(
+            amount >= *min_amount,
+            AmountBelowMinimum { amount, minimum: *min_amount }
+        );
 
         // Hop fees: bonder fee (0.1%) + LP fee (0.04%)
         let bonder_fee = self.calculate_bonder_fee(amount);
@@ -139,27 +176,32 @@ impl IBridgeAdapter for HopBridgeAdapter {
 
     fn bridge_tokens(
         &mut self,
+        _transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
-        _data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        data: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        ensure_deadline(deadline)?;
+        ensure!(
+            native_drop_amount == U256::ZERO,
+            NativeDropNotSupported { adapter: contract::address() }
+        );
+        ensure!(data.is_empty(), PayloadNotSupported { adapter: contract::address() });
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         let hop_bridge = self.get_hop_bridge(token)?;
         let bonder_fee = self.calculate_bonder_fee(amount);
         
-        // Calculate minimum output (0.5% slippage tolerance)
-        let amount_out_min = amount
-            .saturating_mul(U256::from(995))
-            .saturating_div(U256::from(1000));
-
-        // Set deadline to 20 minutes from now
-        let deadline = U256::from(evm::block_timestamp() + 1200);
+        // Hop enforces both on the destination AMM swap
+        let amount_out_min = min_amount_out;
 
         // Create Hop bridge instance
         let bridge = IHopBridge::new(hop_bridge);
@@ -176,30 +218,58 @@ impl IBridgeAdapter for HopBridgeAdapter {
 // Public external interface for router integration
 #[public]
 impl HopBridgeAdapter {
-    pub fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    pub fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         <Self as IBridgeAdapter>::get_bridge_info(self)
     }
 
+    pub fn supports_native(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native(self)
+    }
+
+    pub fn supports_native_drop(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native_drop(self)
+    }
+
+    pub fn supports_payload(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_payload(self)
+    }
+
     pub fn get_route(
         &self,
         from_chain: U256,
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<(String, U256, U256, U256, bool), Vec<u8>> {
+    ) -> Result<(String, U256, U256, U256, bool), SettlementError> {
         let r = <Self as IBridgeAdapter>::get_route(self, from_chain, to_chain, token, amount)?;
         Ok((r.bridge_name, r.estimated_time, r.estimated_gas, r.fee, r.available))
     }
 
     pub fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
         data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
-        let _ = data; // unused
-        <Self as IBridgeAdapter>::bridge_tokens(self, to_chain, token, amount, recipient, Vec::new())
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            <Self as IBridgeAdapter>::bridge_tokens(
+                this,
+                transfer_id,
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop_amount,
+                data,
+            )
+        })
     }
 }
@@ -1,46 +1,75 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloc::{string::String, vec::Vec};
-use stylus_sdk::{msg, prelude::*, storage::StorageMap};
+use stylus_sdk::{contract, msg, prelude::*, storage::StorageMap};
 
-use super::{BridgeRoute, IBridgeAdapter};
+use super::{ensure_deadline, BridgeRoute, IBridgeAdapter};
+use crate::errors::{
+    InsufficientOutput, InvalidAmount, InvalidRecipient, InvalidToken, NativeDropNotSupported,
+    PayloadNotSupported, SettlementError, TokenNotSupported,
+};
+use crate::ownable::Ownable;
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
 
 #[storage]
 pub struct MockBridgeAdapter {
-    owner: Address,
+    #[borrow]
+    ownable: Ownable,
     supported_tokens: StorageMap<Address, bool>,
+    // Held for the duration of every state-mutating call
+    reentrancy_guard: ReentrancyGuard,
+}
+
+impl NonReentrant for MockBridgeAdapter {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
 }
 
 impl MockBridgeAdapter {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
-        instance
+        instance.ownable.init(msg::sender())?;
+        Ok(instance)
     }
 
-    pub fn add_supported_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(token != Address::ZERO, "Invalid token");
+    pub fn add_supported_token(&mut self, token: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
         
-        self.supported_tokens.insert(token, true);
-        Ok(())
+            this.supported_tokens.insert(token, true);
+            Ok(())
+        })
     }
 }
 
 impl IBridgeAdapter for MockBridgeAdapter {
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         Ok(("Mock Bridge".to_string(), true))
     }
 
+    fn supports_native(&self) -> bool {
+        false
+    }
+
+    fn supports_native_drop(&self) -> bool {
+        false
+    }
+
+    fn supports_payload(&self) -> bool {
+        false
+    }
+
     fn get_route(
         &self,
         _from_chain: U256,
         _to_chain: U256,
         token: Address,
         _amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>> {
+    ) -> Result<BridgeRoute, SettlementError> {
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         Ok(BridgeRoute {
@@ -54,21 +83,41 @@ impl IBridgeAdapter for MockBridgeAdapter {
 
     fn bridge_tokens(
         &mut self,
+        _transfer_id: B256,
         _to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
-        _data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
-        ensure!(
-            self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
-        );
-        ensure!(amount > U256::ZERO, "Invalid amount");
-        ensure!(recipient != Address::ZERO, "Invalid recipient");
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        data: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        // No public wrapper takes the lock for the mock, so it is taken here
+        self.non_reentrant(|this| {
+            ensure_deadline(deadline)?;
+            ensure!(
+                native_drop_amount == U256::ZERO,
+                NativeDropNotSupported { adapter: contract::address() }
+            );
+            ensure!(data.is_empty(), PayloadNotSupported { adapter: contract::address() });
+            ensure!(
+                this.supported_tokens.get(&token).unwrap_or(&false),
+                TokenNotSupported { token }
+            );
+            ensure!(amount > U256::ZERO, InvalidAmount {});
+            ensure!(recipient != Address::ZERO, InvalidRecipient {});
+
+            // Flat mock fee, same as quoted by get_route
+            let amount_out = amount.saturating_sub(U256::from(1_000_000));
+            ensure!(
+                amount_out >= min_amount_out,
+                InsufficientOutput { amountOut: amount_out, minAmountOut: min_amount_out }
+            );
 
-        // In a real implementation, this would handle the actual token transfer
-        // and bridge interaction
-        Ok(())
+            // In a real implementation, this would handle the actual token transfer
+            // and bridge interaction
+            Ok(())
+        })
     }
 }
\ No newline at end of file
@@ -8,9 +8,14 @@ pub use mock::MockBridgeAdapter;
 pub use hop::HopBridgeAdapter;
 pub use across::AcrossBridgeAdapter;
 
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloc::{string::String, vec::Vec};
 
+use stylus_sdk::{block, msg};
+
+use crate::errors::{DeadlineExpired, InvalidNativeValue, SettlementError};
+use crate::NATIVE_TOKEN;
+
 /// Bridge Route information
 #[derive(Debug)]
 pub struct BridgeRoute {
@@ -24,7 +29,20 @@ pub struct BridgeRoute {
 /// Bridge Adapter trait defining the interface for cross-chain bridges
 pub trait IBridgeAdapter {
     /// Get information about this bridge adapter
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>>;
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError>;
+
+    /// Whether `bridge_tokens` takes the native asset as `NATIVE_TOKEN` with
+    /// `msg.value`; the router wraps to WETH for adapters that don't
+    fn supports_native(&self) -> bool;
+
+    /// Whether `bridge_tokens` can deliver `native_drop_amount` of destination gas
+    /// to the recipient; the router only routes drops to adapters that can
+    fn supports_native_drop(&self) -> bool;
+
+    /// Whether `bridge_tokens` delivers non-empty `data` to `recipient` through
+    /// the destination receiver's `onSettlement` call; the router only routes
+    /// payloads to adapters that do
+    fn supports_payload(&self) -> bool;
 
     /// Get route information for a cross-chain transfer
     fn get_route(
@@ -33,15 +51,47 @@ pub trait IBridgeAdapter {
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>>;
+    ) -> Result<BridgeRoute, SettlementError>;
 
-    /// Execute a cross-chain transfer
+    /// Execute a cross-chain transfer. Reverts if the recipient would get less
+    /// than `min_amount_out` or `deadline` (unix seconds) has passed. Value sent
+    /// above a native `amount` pays for `native_drop_amount`. Bridges that call
+    /// back on delivery carry `transfer_id` in the `crate::settlement` message.
+    #[allow(clippy::too_many_arguments)]
     fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
         data: Vec<u8>,
-    ) -> Result<(), Vec<u8>>;
+    ) -> Result<(), SettlementError>;
+}
+
+/// Reject a transfer once the user's deadline has passed
+pub(crate) fn ensure_deadline(deadline: U256) -> Result<(), SettlementError> {
+    let now = U256::from(block::timestamp());
+    ensure!(now <= deadline, DeadlineExpired { deadline, timestamp: now });
+    Ok(())
+}
+
+/// Native transfers must carry exactly `amount` as value, ERC-20 transfers none.
+/// A native drop is paid for with extra value on top.
+pub(crate) fn ensure_native_value(
+    token: Address,
+    amount: U256,
+    native_drop_amount: U256,
+) -> Result<(), SettlementError> {
+    let expected = if token == NATIVE_TOKEN { amount } else { U256::ZERO };
+    let received = msg::value();
+    if native_drop_amount == U256::ZERO {
+        ensure!(received == expected, InvalidNativeValue { expected, received });
+    } else {
+        ensure!(received > expected, InvalidNativeValue { expected, received });
+    }
+    Ok(())
 }
\ No newline at end of file
@@ -1,12 +1,32 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use alloc::{string::String, vec::Vec};
-use stylus_sdk::{msg, prelude::*, storage::StorageMap};
+use stylus_sdk::{call::Call, msg, prelude::*, storage::StorageMap};
 
-use crate::adapters::{BridgeRoute, IBridgeAdapter};
+use crate::adapters::{ensure_deadline, ensure_native_value, BridgeRoute, IBridgeAdapter};
+use crate::erc20::ERC20Helper;
+use crate::errors::{
+    AmountBelowMinimum, BridgeNotConfigured, InvalidAddress, InvalidToken, SettlementError,
+    TokenNotSupported,
+};
+use crate::ownable::Ownable;
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
+use crate::settlement::encode_message;
+use crate::NATIVE_TOKEN;
+
+/// Destination gas for the receiver's `sgReceive`
+const SG_RECEIVE_GAS: u64 = 200_000;
 
 /// Stargate Router Interface
 sol_interface! {
     interface IStargateRouter {
+        // Gas for the destination call and the native amount LayerZero airdrops
+        // to `dstNativeAddr`
+        struct lzTxObj {
+            uint256 dstGasForCall;
+            uint256 dstNativeAmount;
+            bytes dstNativeAddr;
+        }
+
         function swap(
             uint16 dstChainId,
             uint256 srcPoolId,
@@ -14,7 +34,8 @@ sol_interface! {
             address payable refundAddress,
             uint256 amountLD,
             uint256 minAmountLD,
-            address to,
+            lzTxObj memory lzTxParams,
+            bytes calldata to,
             bytes calldata payload
         ) external payable;
     }
@@ -22,58 +43,106 @@ sol_interface! {
 
 #[storage]
 pub struct StargateAdapter {
-    owner: Address,
+    #[borrow]
+    ownable: Ownable,
     router: Address,
     supported_tokens: StorageMap<Address, bool>,
     pool_ids: StorageMap<Address, U256>,
     min_amounts: StorageMap<Address, U256>,
+    // Held for the duration of every state-mutating call
+    reentrancy_guard: ReentrancyGuard,
+    // LayerZero chain id and `SettlementReceiver` per destination chain id
+    lz_chain_ids: StorageMap<U256, u16>,
+    receivers: StorageMap<U256, Address>,
+}
+
+impl NonReentrant for StargateAdapter {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
 }
 
 impl StargateAdapter {
-    pub fn new(router: Address) -> Self {
+    pub fn new(router: Address) -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
+        instance.ownable.init(msg::sender())?;
         instance.router = router;
-        instance
+        Ok(instance)
     }
 
-    pub fn add_supported_token(&mut self, token: Address, pool_id: U256, min_amount: U256) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(token != Address::ZERO, "Invalid token");
+    pub fn add_supported_token(&mut self, token: Address, pool_id: U256, min_amount: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
         
-        self.supported_tokens.insert(token, true);
-        self.pool_ids.insert(token, pool_id);
-        self.min_amounts.insert(token, min_amount);
-        Ok(())
+            this.supported_tokens.insert(token, true);
+            this.pool_ids.insert(token, pool_id);
+            this.min_amounts.insert(token, min_amount);
+            Ok(())
+        })
+    }
+
+    /// Bridge to `chain_id` as LayerZero chain `lz_chain_id`, delivering to the
+    /// `SettlementReceiver` there
+    pub fn set_destination(
+        &mut self,
+        chain_id: U256,
+        lz_chain_id: u16,
+        receiver: Address,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(receiver != Address::ZERO, InvalidAddress {});
+            this.lz_chain_ids.insert(chain_id, lz_chain_id);
+            this.receivers.insert(chain_id, receiver);
+            Ok(())
+        })
     }
 
-    fn get_pool_id(&self, token: Address) -> Result<U256, Vec<u8>> {
+    fn get_pool_id(&self, token: Address) -> Result<U256, SettlementError> {
         self.pool_ids
             .get(&token)
             .copied()
-            .ok_or_else(|| "Pool ID not set".as_bytes().to_vec())
+            .ok_or_else(|| BridgeNotConfigured { token }.into())
     }
 }
 
 impl IBridgeAdapter for StargateAdapter {
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         Ok(("Stargate".to_string(), true))
     }
 
+    fn supports_native(&self) -> bool {
+        true
+    }
+
+    // LayerZero airdrops the drop to the recipient
+    fn supports_native_drop(&self) -> bool {
+        true
+    }
+
+    // The payload rides in the `sgReceive` message to the receiver
+    fn supports_payload(&self) -> bool {
+        true
+    }
+
     fn get_route(
         &self,
         _from_chain: U256,
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>> {
+    ) -> Result<BridgeRoute, SettlementError> {
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         let min_amount = self.min_amounts.get(&token).unwrap_or(&U256::ZERO);
-        ensure!(amount >= *min_amount, "Amount below minimum");
+        ensure!(
+            amount >= *min_amount,
+            AmountBelowMinimum { amount, minimum: *min_amount }
+        );
 
         // Stargate fees are typically around 0.06%
         let fee = amount.saturating_mul(U256::from(6)).saturating_div(U256::from(10000));
@@ -89,27 +158,55 @@ impl IBridgeAdapter for StargateAdapter {
 
     fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
-        _data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        data: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        ensure_deadline(deadline)?;
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
-        
-        let pool_id = self.get_pool_id(token)?;
-        let min_amount_out = amount.saturating_mul(U256::from(995)).saturating_div(U256::from(1000)); // 0.5% slippage
+        let receiver = *self.receivers.get(&to_chain).unwrap_or(&Address::ZERO);
+        ensure!(receiver != Address::ZERO, BridgeNotConfigured { token });
+        let dst_chain_id = *self.lz_chain_ids.get(&to_chain).unwrap_or(&0);
 
-        // Create Stargate router instance
-        let router = IStargateRouter::new(self.router);
-
-        // Approve token spend if needed (would need ERC20 interface)
+        // ETH goes to the SGETH pool (pool id registered for NATIVE_TOKEN) as msg.value
+        ensure_native_value(token, amount, native_drop_amount)?;
+        let pool_id = self.get_pool_id(token)?;
+        // Passed to Stargate as minAmountLD; the pool reverts on worse fills
+        let min_amount_ld = min_amount_out;
+        // Handed to the destination receiver's sgReceive with the tokens
+        let payload = encode_message(transfer_id, recipient, amount, &data);
+        // LayerZero airdrops `native_drop_amount` to the recipient, paid from the
+        // value above the bridged amount
+        let lz_tx_obj = IStargateRouter::lzTxObj {
+            dstGasForCall: U256::from(SG_RECEIVE_GAS),
+            dstNativeAmount: native_drop_amount,
+            dstNativeAddr: recipient.to_vec().into(),
+        };
 
-        // Execute the swap - simplified for now
-        // In production, would call router.swap with proper parameters
+        if token != NATIVE_TOKEN {
+            ERC20Helper::approve_token(token, self.router, amount)?;
+        }
+        IStargateRouter::new(self.router).swap(
+            Call::new().value(msg::value()),
+            dst_chain_id,
+            pool_id,
+            pool_id,
+            msg::sender(),
+            amount,
+            min_amount_ld,
+            lz_tx_obj,
+            receiver.to_vec().into(),
+            payload.into(),
+        )?;
 
         Ok(())
     }
@@ -118,30 +215,59 @@ impl IBridgeAdapter for StargateAdapter {
 // Public external interface for router integration
 #[public]
 impl StargateAdapter {
-    pub fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    pub fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         <Self as IBridgeAdapter>::get_bridge_info(self)
     }
 
+    pub fn supports_native(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native(self)
+    }
+
+    pub fn supports_native_drop(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_native_drop(self)
+    }
+
+    pub fn supports_payload(&self) -> bool {
+        <Self as IBridgeAdapter>::supports_payload(self)
+    }
+
     pub fn get_route(
         &self,
         from_chain: U256,
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<(String, U256, U256, U256, bool), Vec<u8>> {
+    ) -> Result<(String, U256, U256, U256, bool), SettlementError> {
         let r = <Self as IBridgeAdapter>::get_route(self, from_chain, to_chain, token, amount)?;
         Ok((r.bridge_name, r.estimated_time, r.estimated_gas, r.fee, r.available))
     }
 
+    #[payable]
     pub fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
         data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
-        let _ = data; // unused
-        <Self as IBridgeAdapter>::bridge_tokens(self, to_chain, token, amount, recipient, Vec::new())
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            <Self as IBridgeAdapter>::bridge_tokens(
+                this,
+                transfer_id,
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop_amount,
+                data,
+            )
+        })
     }
 }
\ No newline at end of file
@@ -1,9 +1,12 @@
 #![cfg(feature = "export-abi")]
 
-use settlement_switch::SettlementSwitch;
+#[cfg(not(feature = "receiver"))]
+use settlement_switch::SettlementSwitch as Contract;
+#[cfg(feature = "receiver")]
+use settlement_switch::receiver::SettlementReceiver as Contract;
 use stylus_sdk::abi::export::print_from_args;
 
 fn main() {
-    print_from_args::<SettlementSwitch>();
+    print_from_args::<Contract>();
 }
 
@@ -1,5 +1,12 @@
-use alloy_primitives::{Address, U256};
-use stylus_sdk::{prelude::*, storage::StorageMap};
+use alloy_primitives::{Address, B256, U256};
+use stylus_sdk::{contract, prelude::*, storage::StorageMap};
+
+use crate::adapters::ensure_deadline;
+use crate::errors::{
+    InsufficientOutput, InvalidAmount, InvalidRecipient, InvalidToken, NativeDropNotSupported,
+    PayloadNotSupported, SettlementError, TokenNotSupported,
+};
+use crate::ownable::Ownable;
 
 #[derive(Debug)]
 pub struct BridgeRoute {
@@ -13,7 +20,20 @@ pub struct BridgeRoute {
 /// Bridge Adapter trait defining the interface for cross-chain bridges
 pub trait IBridgeAdapter {
     /// Get information about this bridge adapter
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>>;
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError>;
+
+    /// Whether `bridge_tokens` takes the native asset as `NATIVE_TOKEN` with
+    /// `msg.value`; the router wraps to WETH for adapters that don't
+    fn supports_native(&self) -> bool;
+
+    /// Whether `bridge_tokens` can deliver `native_drop_amount` of destination gas
+    /// to the recipient; the router only routes drops to adapters that can
+    fn supports_native_drop(&self) -> bool;
+
+    /// Whether `bridge_tokens` delivers non-empty `data` to `recipient` as a
+    /// contract call on the destination; the router only routes payloads to
+    /// adapters that do
+    fn supports_payload(&self) -> bool;
 
     /// Get route information for a cross-chain transfer
     fn get_route(
@@ -22,36 +42,43 @@ pub trait IBridgeAdapter {
         to_chain: U256,
         token: Address,
         amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>>;
+    ) -> Result<BridgeRoute, SettlementError>;
 
-    /// Execute a cross-chain transfer
+    /// Execute a cross-chain transfer. Reverts if the recipient would get less
+    /// than `min_amount_out` or `deadline` (unix seconds) has passed.
+    #[allow(clippy::too_many_arguments)]
     fn bridge_tokens(
         &mut self,
+        transfer_id: B256,
         to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
         data: Vec<u8>,
-    ) -> Result<(), Vec<u8>>;
+    ) -> Result<(), SettlementError>;
 }
 
 // Example implementation of a mock bridge adapter
 #[storage]
 pub struct MockBridgeAdapter {
-    owner: Address,
+    #[borrow]
+    ownable: Ownable,
     supported_tokens: StorageMap<Address, bool>,
 }
 
 impl MockBridgeAdapter {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
-        instance
+        instance.ownable.init(msg::sender())?;
+        Ok(instance)
     }
 
-    pub fn add_supported_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        ensure!(token != Address::ZERO, "Invalid token");
+    pub fn add_supported_token(&mut self, token: Address) -> Result<(), SettlementError> {
+        self.ownable.only_owner()?;
+        ensure!(token != Address::ZERO, InvalidToken {});
         
         self.supported_tokens.insert(token, true);
         Ok(())
@@ -59,20 +86,32 @@ impl MockBridgeAdapter {
 }
 
 impl IBridgeAdapter for MockBridgeAdapter {
-    fn get_bridge_info(&self) -> Result<(String, bool), Vec<u8>> {
+    fn get_bridge_info(&self) -> Result<(String, bool), SettlementError> {
         Ok(("Mock Bridge".to_string(), true))
     }
 
+    fn supports_native(&self) -> bool {
+        false
+    }
+
+    fn supports_native_drop(&self) -> bool {
+        false
+    }
+
+    fn supports_payload(&self) -> bool {
+        false
+    }
+
     fn get_route(
         &self,
         _from_chain: U256,
         _to_chain: U256,
         token: Address,
         _amount: U256,
-    ) -> Result<BridgeRoute, Vec<u8>> {
+    ) -> Result<BridgeRoute, SettlementError> {
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
         );
 
         Ok(BridgeRoute {
@@ -86,18 +125,35 @@ impl IBridgeAdapter for MockBridgeAdapter {
 
     fn bridge_tokens(
         &mut self,
+        _transfer_id: B256,
         _to_chain: U256,
         token: Address,
         amount: U256,
         recipient: Address,
-        _data: Vec<u8>,
-    ) -> Result<(), Vec<u8>> {
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        data: Vec<u8>,
+    ) -> Result<(), SettlementError> {
+        ensure_deadline(deadline)?;
+        ensure!(
+            native_drop_amount == U256::ZERO,
+            NativeDropNotSupported { adapter: contract::address() }
+        );
+        ensure!(data.is_empty(), PayloadNotSupported { adapter: contract::address() });
         ensure!(
             self.supported_tokens.get(&token).unwrap_or(&false),
-            "Token not supported"
+            TokenNotSupported { token }
+        );
+        ensure!(amount > U256::ZERO, InvalidAmount {});
+        ensure!(recipient != Address::ZERO, InvalidRecipient {});
+
+        // Flat mock fee, same as quoted by get_route
+        let amount_out = amount.saturating_sub(U256::from(1_000_000));
+        ensure!(
+            amount_out >= min_amount_out,
+            InsufficientOutput { amountOut: amount_out, minAmountOut: min_amount_out }
         );
-        ensure!(amount > U256::ZERO, "Invalid amount");
-        ensure!(recipient != Address::ZERO, "Invalid recipient");
 
         // In a real implementation, this would handle the actual token transfer
         // and bridge interaction
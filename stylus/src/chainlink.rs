@@ -1,6 +1,8 @@
 use alloy_primitives::{Address, U256};
 use stylus_sdk::prelude::*;
 
+use crate::errors::SettlementError;
+
 /// Chainlink Price Feed ABI interface
 sol_interface! {
     interface IChainlinkAggregator {
@@ -19,7 +21,7 @@ impl ChainlinkPriceFeed {
     }
 
     /// Get the latest price from the feed
-    pub fn get_price(&self) -> Result<U256, Vec<u8>> {
+    pub fn get_price(&self) -> Result<U256, SettlementError> {
         // In production, would call Chainlink aggregator
         // For now, return mock price
         Ok(U256::from(100_000_000)) // $1.00 with 8 decimals
@@ -0,0 +1,47 @@
+//! ERC-1967 implementation slot for upgrades behind a proxy.
+//!
+//! The router is deployed behind a standard ERC-1967 proxy (such as
+//! OpenZeppelin's `ERC1967Proxy`) that delegates every call to the address in
+//! `IMPLEMENTATION_SLOT`. Upgrades are UUPS-style: the implementation itself
+//! rewrites the slot, after checking that the new code answers `proxiableUUID`
+//! (ERC-1822) with the same slot.
+
+use stylus_sdk::{
+    alloy_primitives::{b256, Address, B256, U256},
+    prelude::*,
+};
+
+use crate::errors::{InvalidImplementation, SettlementError};
+use crate::interfaces::{static_call, IERC1822Proxiable};
+
+/// keccak256("eip1967.proxy.implementation") - 1
+pub const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// Get the implementation the proxy delegates to; zero outside a proxy
+pub fn implementation(vm: &dyn Host) -> Address {
+    let slot = U256::from_be_bytes(IMPLEMENTATION_SLOT.0);
+    Address::from_word(vm.storage_load_bytes32(slot))
+}
+
+/// Point the proxy at `implementation`. Fails unless it is a contract reporting
+/// the ERC-1967 implementation slot from `proxiableUUID`.
+pub fn set_implementation(vm: &dyn Host, implementation: Address) -> Result<(), SettlementError> {
+    let invalid = || InvalidImplementation { implementation };
+    if vm.code_size(implementation) == 0 {
+        return Err(invalid().into());
+    }
+    let uuid = static_call(vm, implementation, IERC1822Proxiable::proxiableUUIDCall {})
+        .map_err(|_| invalid())?
+        ._0;
+    if uuid != IMPLEMENTATION_SLOT {
+        return Err(invalid().into());
+    }
+
+    let slot = U256::from_be_bytes(IMPLEMENTATION_SLOT.0);
+    // The slot is far outside the contract's own storage layout, so nothing
+    // else caches it
+    unsafe { vm.storage_cache_bytes32(slot, implementation.into_word()) };
+    vm.flush_cache(false);
+    Ok(())
+}
@@ -1,10 +1,13 @@
 use alloy_primitives::{Address, U256};
 use stylus_sdk::{call::Call, prelude::*};
 
+use crate::errors::SettlementError;
+
 /// ERC20 Token Interface
 sol_interface! {
     interface IERC20 {
         function totalSupply() external view returns (uint256);
+        function decimals() external view returns (uint8);
         function balanceOf(address account) external view returns (uint256);
         function transfer(address recipient, uint256 amount) external returns (bool);
         function allowance(address owner, address spender) external view returns (uint256);
@@ -22,7 +25,7 @@ impl ERC20Helper {
         token: Address,
         spender: Address,
         amount: U256,
-    ) -> Result<(), Vec<u8>> {
+    ) -> Result<(), SettlementError> {
         // In production, would call ERC20 approve
         // For now, assume approval succeeds
         Ok(())
@@ -34,12 +37,19 @@ impl ERC20Helper {
         from: Address,
         to: Address,
         amount: U256,
-    ) -> Result<(), Vec<u8>> {
+    ) -> Result<(), SettlementError> {
         // In production, would call ERC20 transferFrom
         // For now, assume transfer succeeds
         Ok(())
     }
 
+    /// Transfer tokens held by this contract to recipient
+    pub fn transfer(token: Address, to: Address, amount: U256) -> Result<(), SettlementError> {
+        // In production, would call ERC20 transfer
+        // For now, assume transfer succeeds
+        Ok(())
+    }
+
     /// Check token balance
     pub fn balance_of(token: Address, account: Address) -> U256 {
         // In production, would call ERC20 balanceOf
@@ -0,0 +1,162 @@
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{call, prelude::*};
+
+// Custom errors shared by the router, adapters and price oracle
+sol! {
+    #![sol(all_derives)]
+
+    error NotOwner();
+    error NotPendingOwner(address caller);
+    error MissingRole(bytes32 role, address account);
+    error AlreadyInitialized();
+    error ReentrantCall();
+
+    error InvalidOracle();
+    error InvalidAdapter(address adapter);
+    error InvalidToken();
+    error InvalidRecipient();
+    error InvalidAddress();
+    error InvalidAmount();
+    error InvalidNativeValue(uint256 expected, uint256 received);
+    error InvalidChainId();
+    error InvalidGasPrice();
+    error InvalidPriceFeed();
+
+    error AdapterExists(address adapter);
+    error AdapterNotFound(address adapter);
+    error BridgeNotSupported(address adapter);
+    error NativeDropNotSupported(address adapter);
+    error PayloadNotSupported(address adapter);
+    error BridgeNotConfigured(address token);
+    error TokenNotSupported(address token);
+    error TokenTransferFailed(address token);
+    error NoRouteAvailable(uint256 toChain, address token);
+    error AmountBelowMinimum(uint256 amount, uint256 minimum);
+    error InsufficientOutput(uint256 amountOut, uint256 minAmountOut);
+    error DeadlineExpired(uint256 deadline, uint256 timestamp);
+    error UnauthorizedCaller(address caller);
+    error InvalidSettlementMessage();
+    error UntrustedSource(uint8 bridge, uint32 sourceChain, bytes32 source);
+    // No relayed CCTP burn has minted USDC for the transfer yet
+    error SettlementNotFunded(bytes32 transferId, uint256 amount);
+    error TransferAlreadySettled(bytes32 transferId);
+    error TransferNotFound(bytes32 transferId);
+    error NotTransferSender(bytes32 transferId, address caller);
+    error NoRefundAvailable(bytes32 transferId);
+    error RefundTokenMismatch(address pendingToken, address token);
+    error InsufficientRefundBalance(address token, uint256 balance, uint256 required);
+    error FeeTooHigh(uint256 feeBps, uint256 maxFeeBps);
+    error InvalidSpeedWeight(uint256 speedWeightBps);
+    error InvalidMaxRoutes();
+    error InvalidSplitConfig(uint256 maxLegs, uint256 incrementBps);
+    error RoutingPaused();
+    error AdapterPaused(address adapter);
+    error ChainPaused(uint256 chainId);
+    error TokenPaused(address token);
+    error TimelockRequired();
+    error InvalidTimelockDelay(uint256 delay, uint256 maxDelay);
+    error InvalidOperation(uint8 kind);
+    error OperationAlreadyQueued(bytes32 operationId);
+    error OperationNotQueued(bytes32 operationId);
+    error OperationNotReady(bytes32 operationId, uint256 eta);
+    error TransferLimitExceeded(address token, uint256 amount, uint256 maxAmount);
+    // `remaining` is what the limit still allows; `window` is in seconds
+    error RateLimitExceeded(address token, uint256 window, uint256 amount, uint256 remaining);
+    error UserRateLimitExceeded(address user, address token, uint256 amount, uint256 remaining);
+    error ExposureCapExceeded(address adapter, uint256 volumeUsd, uint256 remainingUsd);
+    error InvalidTransferStatus(bytes32 transferId, uint8 status);
+    error AddressBlocked(address account);
+    // The screening oracle reverted; transfers fail closed until it answers
+    error ScreeningFailed(address oracle, address account);
+    error InvalidImplementation(address implementation);
+    // Storage was written by newer code than the running implementation
+    error InvalidStorageVersion(uint256 version, uint256 currentVersion);
+
+    error OracleNotSet();
+    error PriceFeedNotSet();
+    error GasPriceNotSet(uint256 chainId);
+
+    // Revert data bubbled up from an adapter, oracle or token call
+    error ExternalCallFailed(bytes reason);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum SettlementError {
+    NotOwner(NotOwner),
+    NotPendingOwner(NotPendingOwner),
+    MissingRole(MissingRole),
+    AlreadyInitialized(AlreadyInitialized),
+    ReentrantCall(ReentrantCall),
+    InvalidOracle(InvalidOracle),
+    InvalidAdapter(InvalidAdapter),
+    InvalidToken(InvalidToken),
+    InvalidRecipient(InvalidRecipient),
+    InvalidAddress(InvalidAddress),
+    InvalidAmount(InvalidAmount),
+    InvalidNativeValue(InvalidNativeValue),
+    InvalidChainId(InvalidChainId),
+    InvalidGasPrice(InvalidGasPrice),
+    InvalidPriceFeed(InvalidPriceFeed),
+    AdapterExists(AdapterExists),
+    AdapterNotFound(AdapterNotFound),
+    BridgeNotSupported(BridgeNotSupported),
+    NativeDropNotSupported(NativeDropNotSupported),
+    PayloadNotSupported(PayloadNotSupported),
+    BridgeNotConfigured(BridgeNotConfigured),
+    TokenNotSupported(TokenNotSupported),
+    TokenTransferFailed(TokenTransferFailed),
+    NoRouteAvailable(NoRouteAvailable),
+    AmountBelowMinimum(AmountBelowMinimum),
+    InsufficientOutput(InsufficientOutput),
+    DeadlineExpired(DeadlineExpired),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidSettlementMessage(InvalidSettlementMessage),
+    UntrustedSource(UntrustedSource),
+    SettlementNotFunded(SettlementNotFunded),
+    TransferAlreadySettled(TransferAlreadySettled),
+    TransferNotFound(TransferNotFound),
+    NotTransferSender(NotTransferSender),
+    NoRefundAvailable(NoRefundAvailable),
+    RefundTokenMismatch(RefundTokenMismatch),
+    InsufficientRefundBalance(InsufficientRefundBalance),
+    FeeTooHigh(FeeTooHigh),
+    InvalidSpeedWeight(InvalidSpeedWeight),
+    InvalidMaxRoutes(InvalidMaxRoutes),
+    InvalidSplitConfig(InvalidSplitConfig),
+    RoutingPaused(RoutingPaused),
+    AdapterPaused(AdapterPaused),
+    ChainPaused(ChainPaused),
+    TokenPaused(TokenPaused),
+    TimelockRequired(TimelockRequired),
+    InvalidTimelockDelay(InvalidTimelockDelay),
+    InvalidOperation(InvalidOperation),
+    OperationAlreadyQueued(OperationAlreadyQueued),
+    OperationNotQueued(OperationNotQueued),
+    OperationNotReady(OperationNotReady),
+    TransferLimitExceeded(TransferLimitExceeded),
+    RateLimitExceeded(RateLimitExceeded),
+    UserRateLimitExceeded(UserRateLimitExceeded),
+    ExposureCapExceeded(ExposureCapExceeded),
+    InvalidTransferStatus(InvalidTransferStatus),
+    AddressBlocked(AddressBlocked),
+    ScreeningFailed(ScreeningFailed),
+    InvalidImplementation(InvalidImplementation),
+    InvalidStorageVersion(InvalidStorageVersion),
+    OracleNotSet(OracleNotSet),
+    PriceFeedNotSet(PriceFeedNotSet),
+    GasPriceNotSet(GasPriceNotSet),
+    ExternalCallFailed(ExternalCallFailed),
+}
+
+// `sol_interface!` calls still return the deprecated `call::Error` in this SDK version
+#[allow(deprecated)]
+impl From<call::Error> for SettlementError {
+    fn from(err: call::Error) -> Self {
+        let reason = match err {
+            call::Error::Revert(data) => data,
+            call::Error::AbiDecodingFailed(_) => Vec::new(),
+        };
+        ExternalCallFailed { reason: reason.into() }.into()
+    }
+}
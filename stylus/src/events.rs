@@ -0,0 +1,111 @@
+use alloy_sol_types::sol;
+
+// Events emitted by the router contracts. Indexers key transfers on `transferId`.
+sol! {
+    event BridgeAdapterAdded(address indexed adapter);
+    event BridgeAdapterRemoved(address indexed adapter);
+
+    event TokenSupportUpdated(address indexed token, bool supported);
+
+    event PriceOracleUpdated(address indexed previousOracle, address indexed newOracle);
+    event WethUpdated(address indexed previousWeth, address indexed newWeth);
+    event ValueOfTimeUpdated(uint256 previousValue, uint256 newValue);
+    event MaxRoutesUpdated(uint256 previousValue, uint256 newValue);
+    event SplitConfigUpdated(uint256 maxLegs, uint256 incrementBps);
+
+    event ProtocolFeeUpdated(uint256 previousFeeBps, uint256 newFeeBps);
+    event FeeRecipientUpdated(address indexed previousRecipient, address indexed newRecipient);
+    event FeesWithdrawn(address indexed token, address indexed recipient, uint256 amount);
+    event MaxIntegratorFeeUpdated(uint256 previousFeeBps, uint256 newFeeBps);
+    event IntegratorFeesClaimed(address indexed integrator, address indexed token, uint256 amount);
+
+    event Paused(address indexed account);
+    event Unpaused(address indexed account);
+    event AdapterPauseUpdated(address indexed adapter, bool paused, address indexed account);
+    event ChainPauseUpdated(uint256 indexed chainId, bool paused, address indexed account);
+    event TokenPauseUpdated(address indexed token, bool paused, address indexed account);
+
+    event TimelockDelayUpdated(uint256 previousDelay, uint256 newDelay);
+    event OperationQueued(bytes32 indexed operationId, uint8 kind, uint256 value, uint256 eta);
+    event OperationExecuted(bytes32 indexed operationId, uint8 kind, uint256 value);
+    event OperationCancelled(bytes32 indexed operationId, uint8 kind, uint256 value);
+
+    event RateLimitsUpdated(
+        address indexed token,
+        uint256 maxTransfer,
+        uint256 hourlyLimit,
+        uint256 dailyLimit,
+        uint256 userDailyLimit
+    );
+
+    event AdapterExposureCapUpdated(address indexed adapter, uint256 capUsd);
+    // `status` is 2 for completed and 3 for failed
+    event TransferStatusUpdated(bytes32 indexed transferId, address indexed adapter, uint8 status);
+
+    event ScreeningOracleUpdated(address indexed previousOracle, address indexed newOracle);
+    event DenylistUpdated(address indexed account, bool denied);
+
+    // ERC-1967
+    event Upgraded(address indexed implementation);
+    event StorageMigrated(uint256 fromVersion, uint256 toVersion);
+
+    event OwnershipTransferStarted(address indexed previousOwner, address indexed newOwner);
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+
+    event CrossChainTransferInitiated(
+        bytes32 indexed transferId,
+        address indexed sender,
+        address indexed recipient,
+        address bridgeAdapter,
+        address token,
+        uint256 amount,
+        uint256 toChain,
+        address integrator
+    );
+
+    // A skipped `execute_batch` leg; `amount` went back to the sender
+    event BatchLegRefunded(
+        uint256 indexed index,
+        address indexed sender,
+        address token,
+        uint256 amount,
+        address recipient
+    );
+
+    // A bridge pushed `amount` back to the router for `transferId`; its sender can claim it
+    event RefundProcessed(bytes32 indexed transferId, address token, uint256 amount);
+    event RefundClaimed(
+        bytes32 indexed transferId,
+        address indexed sender,
+        address token,
+        uint256 amount
+    );
+
+    // Emitted by `SettlementReceiver` on the destination chain. `bridge` is
+    // 1 for Stargate, 2 for Across and 3 for CCTP; unverified (Across)
+    // settlements are provisional and may be replaced.
+    event TransferSettled(
+        bytes32 indexed transferId,
+        address indexed recipient,
+        address token,
+        uint256 amount,
+        uint8 bridge,
+        bool verified
+    );
+    event ReceiverEndpointsUpdated(
+        address stargateRouter,
+        address acrossSpokePool,
+        address cctpMessageTransmitter,
+        address cctpTokenMessenger,
+        address cctpToken
+    );
+    event TrustedSourceUpdated(
+        uint8 indexed bridge,
+        uint32 indexed sourceChain,
+        bytes32 indexed source,
+        bool trusted
+    );
+}
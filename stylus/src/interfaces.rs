@@ -0,0 +1,155 @@
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+    stylus_core::calls::{context::Call, errors::Error},
+};
+
+use crate::errors::{ExternalCallFailed, SettlementError, TokenTransferFailed};
+
+// ABIs of the contracts the router calls by address. Stylus exports camelCase
+// selectors, so these match the adapters and oracle in this crate.
+sol! {
+    interface IBridgeAdapterExternal {
+        function getBridgeInfo() external view returns (string, bool);
+        function supportsNative() external view returns (bool);
+        function supportsNativeDrop() external view returns (bool);
+        function supportsPayload() external view returns (bool);
+        function getRoute(uint256 fromChain, uint256 toChain, address token, uint256 amount)
+            external view returns (
+                string bridgeName,
+                uint256 estimatedTime,
+                uint256 estimatedGas,
+                uint256 fee,
+                bool available
+            );
+        function bridgeTokens(
+            bytes32 transferId,
+            uint256 toChain,
+            address token,
+            uint256 amount,
+            address recipient,
+            uint256 minAmountOut,
+            uint256 deadline,
+            uint256 nativeDropAmount,
+            bytes data
+        ) external payable;
+    }
+
+    interface IPriceOracleExternal {
+        function getTokenPrice(address token) external view returns (uint256);
+        function getGasPrice(uint256 chainId) external view returns (uint256);
+        function getNativeTokenPrice(uint256 chainId) external view returns (uint256);
+        function calculateGasCost(uint256 chainId, uint256 gasAmount) external view returns (uint256);
+    }
+
+    // Chainalysis sanctions oracle
+    interface ISanctionsList {
+        function isSanctioned(address addr) external view returns (bool);
+    }
+
+    // Answered by UUPS implementations with the ERC-1967 implementation slot
+    interface IERC1822Proxiable {
+        function proxiableUUID() external view returns (bytes32);
+    }
+
+    // CCTP v2 message transmitter, which mints burned USDC on receipt
+    interface IMessageTransmitter {
+        function receiveMessage(bytes message, bytes attestation) external returns (bool);
+    }
+
+    // Called by `SettlementReceiver` on recipients of a settlement with a payload
+    interface ISettlementRecipient {
+        function onSettlement(bytes32 transferId, address token, uint256 amount, bytes payload)
+            external;
+    }
+
+    interface IERC20Metadata {
+        function decimals() external view returns (uint8);
+    }
+
+    interface IWETH {
+        function deposit() external payable;
+        function withdraw(uint256 amount) external;
+    }
+
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+}
+
+impl From<Error> for SettlementError {
+    fn from(err: Error) -> Self {
+        let reason = match err {
+            Error::Revert(data) => data,
+            Error::AbiDecodingFailed(_) => Vec::new(),
+        };
+        ExternalCallFailed { reason: reason.into() }.into()
+    }
+}
+
+/// Static-call `to` and decode the return values of `call`
+pub fn static_call<C: SolCall>(
+    vm: &dyn Host,
+    to: Address,
+    call: C,
+) -> Result<C::Return, SettlementError> {
+    let data = vm.static_call(&Call::new(), to, &call.abi_encode())?;
+    decode_returns::<C>(&data)
+}
+
+/// Call `to` without value and decode the return values of `call`
+pub fn call_contract<C: SolCall>(
+    vm: &dyn Host,
+    to: Address,
+    call: C,
+) -> Result<C::Return, SettlementError> {
+    let data = vm.call(&Call::new(), to, &call.abi_encode())?;
+    decode_returns::<C>(&data)
+}
+
+/// Call `to` sending `value` wei and decode the return values of `call`
+pub fn call_with_value<C: SolCall>(
+    vm: &dyn Host,
+    to: Address,
+    value: U256,
+    call: C,
+) -> Result<C::Return, SettlementError> {
+    let data = vm.call(&Call::new().value(value), to, &call.abi_encode())?;
+    decode_returns::<C>(&data)
+}
+
+/// Send `amount` wei of the native asset to `to`
+pub fn send_native(vm: &dyn Host, to: Address, amount: U256) -> Result<(), SettlementError> {
+    vm.call(&Call::new().value(amount), to, &[])?;
+    Ok(())
+}
+
+/// Call an ERC-20 method returning `bool`. Tokens that return nothing (USDT)
+/// count as success; `false` is an error. So is an account without code, where
+/// every call succeeds with no data.
+pub fn call_token<C: SolCall>(vm: &dyn Host, token: Address, call: C) -> Result<(), SettlementError> {
+    let data = vm.call(&Call::new(), token, &call.abi_encode())?;
+    let succeeded = if data.is_empty() {
+        vm.code_size(token) > 0
+    } else {
+        bool::abi_decode(&data, true) == Ok(true)
+    };
+    if succeeded {
+        return Ok(());
+    }
+    Err(TokenTransferFailed { token }.into())
+}
+
+fn decode_returns<C: SolCall>(data: &[u8]) -> Result<C::Return, SettlementError> {
+    C::abi_decode_returns(data, true).map_err(|_| {
+        ExternalCallFailed {
+            reason: Vec::new().into(),
+        }
+        .into()
+    })
+}
@@ -1,17 +1,212 @@
-#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
-#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
 
 extern crate alloc;
 
+pub mod access_control;
+pub mod erc1967;
+pub mod errors;
+pub mod events;
+pub mod interfaces;
+pub mod ownable;
+pub mod rate_limit;
+pub mod receiver;
+pub mod reentrancy_guard;
+pub mod settlement;
+
 use alloc::{string::String, vec, vec::Vec};
+use alloy_sol_types::SolValue;
 use stylus_sdk::{
-    alloy_primitives::{Address, U256},
-    msg,
+    abi::Bytes,
+    alloy_primitives::{address, Address, B256, U256, U8},
+    crypto::keccak,
     prelude::*,
 };
 
+use crate::errors::{
+    AdapterExists, AdapterNotFound, AdapterPaused, AddressBlocked, ChainPaused, DeadlineExpired,
+    ExposureCapExceeded, FeeTooHigh, InsufficientOutput, InsufficientRefundBalance,
+    InvalidAdapter, InvalidAddress, InvalidAmount, InvalidMaxRoutes, InvalidNativeValue,
+    InvalidOperation, InvalidOracle, InvalidPriceFeed, InvalidRecipient, InvalidSpeedWeight,
+    InvalidSplitConfig, InvalidStorageVersion, InvalidTimelockDelay, InvalidToken,
+    InvalidTransferStatus, NativeDropNotSupported, NoRefundAvailable, NoRouteAvailable,
+    NotTransferSender, OperationAlreadyQueued, OperationNotQueued, OperationNotReady,
+    PayloadNotSupported, RateLimitExceeded, RefundTokenMismatch, RoutingPaused, ScreeningFailed,
+    SettlementError, TimelockRequired, TokenNotSupported, TokenPaused, TransferLimitExceeded,
+    TransferNotFound, UnauthorizedCaller, UserRateLimitExceeded,
+};
+use crate::events::{
+    AdapterExposureCapUpdated, AdapterPauseUpdated, BatchLegRefunded, BridgeAdapterAdded,
+    BridgeAdapterRemoved, ChainPauseUpdated, CrossChainTransferInitiated, DenylistUpdated,
+    FeeRecipientUpdated, FeesWithdrawn, IntegratorFeesClaimed, MaxIntegratorFeeUpdated,
+    MaxRoutesUpdated, OperationCancelled, OperationExecuted, OperationQueued, Paused,
+    PriceOracleUpdated, ProtocolFeeUpdated, RateLimitsUpdated, RefundClaimed, RefundProcessed,
+    ScreeningOracleUpdated, SplitConfigUpdated, StorageMigrated, TimelockDelayUpdated,
+    TokenPauseUpdated, TokenSupportUpdated, TransferStatusUpdated, Unpaused, Upgraded,
+    ValueOfTimeUpdated, WethUpdated,
+};
+use crate::interfaces::{
+    call_contract, call_token, call_with_value, send_native, static_call, IBridgeAdapterExternal,
+    IERC20Metadata, IPriceOracleExternal, ISanctionsList, IERC20, IWETH,
+};
+use crate::access_control::{
+    AccessControl, ADAPTER_MANAGER_ROLE, ADMIN_ROLE, FEE_MANAGER_ROLE, PAUSER_ROLE, RELAYER_ROLE,
+};
+use crate::ownable::Ownable;
+use crate::rate_limit::{RateBucket, DAY, HOUR};
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
+
+/// Sentinel token address for the chain's native asset
+pub const NATIVE_TOKEN: Address = address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
+
+/// Split quote returned by `get_split_quote`: (adapters, amounts, amounts_out, total_amount_out)
+pub type SplitQuote = (Vec<Address>, Vec<U256>, Vec<U256>, U256);
+
+/// One `execute_batch` leg: (to_chain, token, amount, recipient, min_amount_out)
+pub type BatchLeg = (U256, Address, U256, Address, U256);
+
+/// Route quote returned by `get_routes`.
+///
+/// (adapter, name, estimated_time, gas_cost_usd, bridge_fee, bridge_fee_usd, protocol_fee,
+/// protocol_fee_usd, integrator_fee, integrator_fee_usd, native_drop_usd, total_cost_usd,
+/// amount_out, available). USD values have 8 decimals.
+pub type RouteQuote =
+    (Address, String, U256, U256, U256, U256, U256, U256, U256, U256, U256, U256, U256, bool);
+
+/// Upper bound for `speed_weight_bps`: 0 picks the cheapest route, 10_000 the fastest
+const MAX_SPEED_WEIGHT_BPS: u64 = 10_000;
+
+/// Value of one second of transfer time until the owner sets one: $0.0001 (8 decimals)
+const DEFAULT_VALUE_OF_TIME: u64 = 10_000;
+
+/// Routes returned by `get_routes` until the owner sets a limit
+const DEFAULT_MAX_ROUTES: u64 = 5;
+
+/// Basis points in 100%
+const MAX_BPS: u64 = 10_000;
+
+/// Split quotes use up to 3 adapters in 25% slices until the owner configures them
+const DEFAULT_MAX_SPLIT_LEGS: u64 = 3;
+const DEFAULT_SPLIT_INCREMENT_BPS: u64 = 2_500;
+
+/// Ceiling for the protocol fee: 1% of the transfer amount
+const MAX_PROTOCOL_FEE_BPS: u64 = 100;
+
+/// Ceiling for the owner-set integrator fee maximum: 3% of the transfer amount
+const MAX_INTEGRATOR_FEE_BPS: u64 = 300;
+
+/// Longest timelock delay the owner can set: 30 days
+const MAX_TIMELOCK_DELAY: u64 = 30 * 24 * 60 * 60;
+
+/// Timelocked operation kinds for `queue_operation`. The value is the setter's
+/// argument, with addresses as their uint256 form; renouncing ignores it.
+pub const OP_ADD_BRIDGE_ADAPTER: u8 = 1;
+pub const OP_SET_PRICE_ORACLE: u8 = 2;
+pub const OP_SET_PROTOCOL_FEE: u8 = 3;
+pub const OP_SET_FEE_RECIPIENT: u8 = 4;
+pub const OP_SET_MAX_INTEGRATOR_FEE: u8 = 5;
+pub const OP_TRANSFER_OWNERSHIP: u8 = 6;
+pub const OP_RENOUNCE_OWNERSHIP: u8 = 7;
+pub const OP_SET_TIMELOCK_DELAY: u8 = 8;
+pub const OP_SET_SCREENING_ORACLE: u8 = 9;
+pub const OP_UPGRADE_TO: u8 = 10;
+pub const OP_SET_WETH: u8 = 11;
+
+/// Storage layout version of this code. Bump it, with a step in `migrate_from`,
+/// whenever an upgrade needs existing storage rewritten.
+pub const STORAGE_VERSION: u64 = 1;
+
+/// Transfer statuses: pending once dispatched, then completed or failed as
+/// reported by a relayer
+pub const TRANSFER_PENDING: u8 = 1;
+pub const TRANSFER_COMPLETED: u8 = 2;
+pub const TRANSFER_FAILED: u8 = 3;
+
+/// Route scores rise 10% per recent failure of the adapter, for up to 5 failures.
+/// Failures stay recent until a day passes without another.
+const FAILURE_PENALTY_BPS: u64 = 1_000;
+const MAX_PENALIZED_FAILURES: u64 = 5;
+
+/// A transfer handed to a bridge adapter, with the user's execution bounds
+struct Transfer {
+    to_chain: U256,
+    token: Address,
+    amount: U256,
+    recipient: Address,
+    min_amount_out: U256,
+    deadline: U256,
+    // Destination native gas for the recipient, and the source value paying for it
+    native_drop: U256,
+    native_drop_value: U256,
+    // Delivered with the funds to `recipient` as a contract call on the destination
+    payload: Vec<u8>,
+    // Partner wallet taking `integrator_fee_bps` of the amount; zero for none
+    integrator: Address,
+    integrator_fee_bps: U256,
+}
+
+/// Destination features a route must support to be available, and the
+/// integrator's cut taken from the amount before quoting
+#[derive(Clone, Copy)]
+struct RouteOptions {
+    native_drop: U256,
+    with_payload: bool,
+    integrator_fee_bps: U256,
+}
+
+impl Transfer {
+    fn route_options(&self) -> RouteOptions {
+        RouteOptions {
+            native_drop: self.native_drop,
+            with_payload: !self.payload.is_empty(),
+            integrator_fee_bps: self.integrator_fee_bps,
+        }
+    }
+}
+
+/// A route priced in USD (8 decimals)
+struct PricedRoute {
+    adapter: Address,
+    bridge_name: String,
+    estimated_time: U256,
+    gas_cost_usd: U256,
+    bridge_fee: U256,
+    bridge_fee_usd: U256,
+    // Taken from the input amount before bridging, in token units
+    protocol_fee: U256,
+    protocol_fee_usd: U256,
+    integrator_fee: U256,
+    integrator_fee_usd: U256,
+    native_drop_usd: U256,
+    total_cost_usd: U256,
+    amount_out: U256,
+    // False if the adapter cannot deliver the requested native drop or payload
+    available: bool,
+}
+
+impl PricedRoute {
+    fn into_quote(self) -> RouteQuote {
+        (
+            self.adapter,
+            self.bridge_name,
+            self.estimated_time,
+            self.gas_cost_usd,
+            self.bridge_fee,
+            self.bridge_fee_usd,
+            self.protocol_fee,
+            self.protocol_fee_usd,
+            self.integrator_fee,
+            self.integrator_fee_usd,
+            self.native_drop_usd,
+            self.total_cost_usd,
+            self.amount_out,
+            self.available,
+        )
+    }
+}
+
 sol_storage! {
-    #[entrypoint]
+    #[cfg_attr(not(feature = "receiver"), entrypoint)]
     pub struct SettlementSwitch {
         // Price oracle for gas and token prices
         address price_oracle;
@@ -23,154 +218,4621 @@ sol_storage! {
         uint256 total_transfers;
         uint256 total_volume_usd;
 
-        // Owner
-        address owner;
+        // Owner and pending owner
+        #[borrow]
+        Ownable ownable;
+
+        // Registered bridge adapters
+        address[] bridge_adapters;
+        mapping(address => bool) is_bridge_adapter;
+
+        // Token allowlist
+        mapping(address => bool) supported_tokens;
+        address[] token_list;
+
+        // USD value of one second of transfer time (8 decimals), used by speed-weighted scoring
+        uint256 value_of_time;
+
+        // Maximum number of routes returned by get_routes
+        uint256 max_routes;
+
+        // Split routing: adapters per transfer and allocation step (bps of amount)
+        uint256 max_split_legs;
+        uint256 split_increment_bps;
+
+        // Wrapped native token, used for native transfers through ERC-20-only adapters
+        address weth;
+
+        // Dispatched transfers by id: original sender, token and amount
+        mapping(bytes32 => address) transfer_senders;
+        mapping(bytes32 => address) transfer_tokens;
+        mapping(bytes32 => uint256) transfer_amounts;
+
+        // Refunds bridges pushed back to the router, claimable by the transfer's sender
+        mapping(bytes32 => address) refund_tokens;
+        mapping(bytes32 => uint256) refund_amounts;
+        // Unclaimed refunds per token; process_refund never credits more than is held
+        mapping(address => uint256) refund_liabilities;
+
+        // Protocol fee on the input amount, accrued per token until withdrawn
+        uint256 protocol_fee_bps;
+        address fee_recipient;
+        mapping(address => uint256) accrued_fees;
+
+        // Integrator fees: owner-set cap, unclaimed fees per integrator and token
+        // (and their total per token), and per-integrator transfer stats
+        uint256 max_integrator_fee_bps;
+        mapping(address => mapping(address => uint256)) integrator_fees;
+        mapping(address => uint256) integrator_fee_liabilities;
+        mapping(address => uint256) integrator_transfers;
+        mapping(address => uint256) integrator_volume_usd;
+
+        // Role members; the deployer starts as admin
+        #[borrow]
+        AccessControl access_control;
+
+        // Emergency stops: pausers set them, only the owner clears them
+        bool paused;
+        mapping(address => bool) paused_adapters;
+        mapping(uint256 => bool) paused_chains;
+        mapping(address => bool) paused_tokens;
+
+        // Timelock: zero delay applies sensitive changes directly; otherwise they
+        // are queued by operation id and executable from the stored eta
+        uint256 timelock_delay;
+        mapping(bytes32 => uint256) operation_etas;
+
+        // Held for the duration of every state-mutating call
+        ReentrancyGuard reentrancy_guard;
+
+        // Rate limits per token, zero for unlimited: largest single transfer, rolling
+        // hourly and daily volume, and rolling daily volume per sender
+        mapping(address => uint256) max_transfer_amounts;
+        mapping(address => uint256) hourly_limits;
+        mapping(address => uint256) daily_limits;
+        mapping(address => uint256) user_daily_limits;
+        mapping(address => RateBucket) hourly_usage;
+        mapping(address => RateBucket) daily_usage;
+        mapping(address => mapping(address => RateBucket)) user_daily_usage;
+
+        // Adapter risk: owner-set cap on in-flight USD volume (zero for none), the
+        // volume still in flight, and outcomes from relayer status reports with
+        // the current run of recent failures
+        mapping(address => uint256) adapter_exposure_caps;
+        mapping(address => uint256) adapter_exposure;
+        mapping(address => uint256) adapter_successes;
+        mapping(address => uint256) adapter_failures;
+        mapping(address => uint256) adapter_recent_failures;
+        mapping(address => uint256) adapter_last_failure;
+
+        // Dispatched transfers by id: adapter, USD volume in flight and status
+        mapping(bytes32 => address) transfer_adapters;
+        mapping(bytes32 => uint256) transfer_volumes_usd;
+        mapping(bytes32 => uint8) transfer_statuses;
+
+        // Screening: optional sanctions oracle and owner-managed denylist, checked
+        // for the sender and recipient of every quote and transfer
+        address screening_oracle;
+        mapping(address => bool) denylist;
+
+        // Layout version the storage was last migrated to; zero before versioning
+        uint256 storage_version;
     }
 }
 
 #[public]
+#[inherit(Ownable, AccessControl)]
 impl SettlementSwitch {
     /// Initialize the router with a price oracle
     #[allow(non_snake_case)]
-    pub fn initialize(&mut self, price_oracle: Address) -> Result<(), Vec<u8>> {
-        if self.owner.get() != Address::ZERO {
-            return Err(b"Already initialized".to_vec());
-        }
-        if price_oracle == Address::ZERO {
-            return Err(b"Invalid oracle".to_vec());
-        }
+    pub fn initialize(&mut self, price_oracle: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            let owner = this.vm().msg_sender();
+            this.ownable.init(owner)?;
+            if price_oracle == Address::ZERO {
+                return Err(InvalidOracle {}.into());
+            }
 
-        self.owner.set(msg::sender());
-        self.price_oracle.set(price_oracle);
+            this.access_control.init(owner);
+            this.price_oracle.set(price_oracle);
+            this.storage_version.set(U256::from(STORAGE_VERSION));
 
-        Ok(())
+            log(this.vm(), PriceOracleUpdated {
+                previousOracle: Address::ZERO,
+                newOracle: price_oracle,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Replace the price oracle (timelocked)
+    #[allow(non_snake_case)]
+    pub fn set_price_oracle(&mut self, price_oracle: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_price_oracle(price_oracle)
+        })
     }
 
-    /// Add a bridge adapter
+    /// Add a bridge adapter (timelocked)
     #[allow(non_snake_case)]
-    pub fn add_bridge_adapter(&mut self, _adapter: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        if _adapter == Address::ZERO {
-            return Err(b"Invalid adapter".to_vec());
+    pub fn add_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADAPTER_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_bridge_adapter(adapter)
+        })
+    }
+
+    /// Remove a bridge adapter
+    #[allow(non_snake_case)]
+    pub fn remove_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADAPTER_MANAGER_ROLE)?;
+            if !this.is_bridge_adapter.get(adapter) {
+                return Err(AdapterNotFound { adapter }.into());
+            }
+
+            // Swap with the last entry and pop; order is not significant
+            let len = this.bridge_adapters.len();
+            for i in 0..len {
+                if this.bridge_adapters.get(i) == Some(adapter) {
+                    let last = this.bridge_adapters.get(len - 1).unwrap();
+                    this.bridge_adapters.setter(i).unwrap().set(last);
+                    this.bridge_adapters.pop();
+                    break;
+                }
+            }
+            this.is_bridge_adapter.insert(adapter, false);
+
+            let count = this.adapter_count.get();
+            this.adapter_count.set(count - U256::from(1));
+
+            log(this.vm(), BridgeAdapterRemoved { adapter });
+
+            Ok(())
+        })
+    }
+
+    /// Enable or disable routing for a token
+    #[allow(non_snake_case)]
+    pub fn set_token_support(
+        &mut self,
+        token: Address,
+        supported: bool,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            if token == Address::ZERO {
+                return Err(InvalidToken {}.into());
+            }
+            if this.supported_tokens.get(token) == supported {
+                return Ok(());
+            }
+
+            if supported {
+                this.token_list.push(token);
+            } else {
+                let len = this.token_list.len();
+                for i in 0..len {
+                    if this.token_list.get(i) == Some(token) {
+                        let last = this.token_list.get(len - 1).unwrap();
+                        this.token_list.setter(i).unwrap().set(last);
+                        this.token_list.pop();
+                        break;
+                    }
+                }
+            }
+            this.supported_tokens.insert(token, supported);
+
+            log(this.vm(), TokenSupportUpdated { token, supported });
+
+            Ok(())
+        })
+    }
+
+    /// Set the USD value of one second of transfer time (8 decimals)
+    #[allow(non_snake_case)]
+    pub fn set_value_of_time(&mut self, value_per_second: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+
+            let previous = this.value_of_time.get();
+            this.value_of_time.set(value_per_second);
+
+            log(this.vm(), ValueOfTimeUpdated {
+                previousValue: previous,
+                newValue: value_per_second,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get the value of time used for scoring, falling back to the default
+    #[allow(non_snake_case)]
+    pub fn get_value_of_time(&self) -> U256 {
+        let value = self.value_of_time.get();
+        if value == U256::ZERO {
+            U256::from(DEFAULT_VALUE_OF_TIME)
+        } else {
+            value
         }
+    }
 
-        // Increment adapter count
-        let count = self.adapter_count.get();
-        self.adapter_count.set(count + U256::from(1));
+    /// Set the wrapped native token (timelocked). Zero disables wrapping, so
+    /// native transfers only use adapters that bridge the native asset directly.
+    #[allow(non_snake_case)]
+    pub fn set_weth(&mut self, weth: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_weth(weth)
+        })
+    }
+
+    /// Get the wrapped native token
+    #[allow(non_snake_case)]
+    pub fn get_weth(&self) -> Address {
+        self.weth.get()
+    }
 
+    /// Accept ETH from WETH unwrapping a failed leg and native refunds pushed back
+    /// by registered adapters. Not guarded: both can call back while the router
+    /// holds the reentrancy lock.
+    #[receive]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        if sender != self.weth.get() && !self.is_bridge_adapter.get(sender) {
+            return Err(SettlementError::from(UnauthorizedCaller { caller: sender }).into());
+        }
         Ok(())
     }
 
-    /// Get all available routes for a transfer (mock implementation)
+    /// Set the protocol fee taken from every transfer's input amount, at most 1%
+    /// (timelocked)
     #[allow(non_snake_case)]
-    pub fn get_routes(
-        &self,
-        _from_chain: U256,
-        _to_chain: U256,
-        _token: Address,
-        amount: U256,
-        _recipient: Address,
-    ) -> Result<Vec<(Address, String, U256, U256, U256, U256, U256, bool)>, Vec<u8>> {
-        if amount == U256::ZERO {
-            return Err(b"Invalid amount".to_vec());
-        }
+    pub fn set_protocol_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_protocol_fee(fee_bps)
+        })
+    }
 
-        let mut routes = Vec::new();
-        let adapter_count = self.adapter_count.get();
+    /// Get the protocol fee in basis points
+    #[allow(non_snake_case)]
+    pub fn get_protocol_fee(&self) -> U256 {
+        self.protocol_fee_bps.get()
+    }
 
-        // Return one mock route per adapter
-        if adapter_count > U256::ZERO {
-            let count = adapter_count.as_limbs()[0] as usize;
-            for i in 0..count {
-                let bridge_name = match i {
-                    0 => String::from("Stargate"),
-                    1 => String::from("Hop Protocol"),
-                    2 => String::from("Across Protocol"),
-                    _ => String::from("Mock Bridge"),
-                };
-                
-                // Vary the costs slightly for each bridge
-                let time_multiplier = U256::from(1 + i as u64);
-                let cost_multiplier = U256::from(1 + (i as u64 * 2));
-                
-                routes.push((
-                    Address::ZERO, // Mock adapter address
-                    bridge_name,
-                    U256::from(300) * time_multiplier,                    // estimated_time (5-15 min)
-                    U256::from(100_000) * cost_multiplier,                // estimated_gas_cost
-                    amount / U256::from(1000),                            // bridge_fee (0.1%)
-                    U256::from(1_000_000) * cost_multiplier,              // total_cost_usd
-                    amount - (amount / U256::from(1000)),                 // amount_out
-                    true,                                                  // available
-                ));
+    /// Set the address `withdraw_fees` pays out to (timelocked)
+    #[allow(non_snake_case)]
+    pub fn set_fee_recipient(&mut self, recipient: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_fee_recipient(recipient)
+        })
+    }
+
+    /// Get the fee recipient
+    #[allow(non_snake_case)]
+    pub fn get_fee_recipient(&self) -> Address {
+        self.fee_recipient.get()
+    }
+
+    /// Get the protocol fees accrued in `token` and not yet withdrawn
+    #[allow(non_snake_case)]
+    pub fn get_accrued_fees(&self, token: Address) -> U256 {
+        self.accrued_fees.get(token)
+    }
+
+    /// Send the protocol fees accrued in `token` to the fee recipient. Fee
+    /// managers can withdraw, but only the owner can change the recipient.
+    #[allow(non_snake_case)]
+    pub fn withdraw_fees(&mut self, token: Address) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            let recipient = this.fee_recipient.get();
+            if recipient == Address::ZERO {
+                return Err(InvalidAddress {}.into());
             }
-        }
+            let amount = this.accrued_fees.get(token);
+            if amount == U256::ZERO {
+                return Err(InvalidAmount {}.into());
+            }
+
+            this.accrued_fees.setter(token).set(U256::ZERO);
+            this.send_tokens(token, recipient, amount)?;
 
-        Ok(routes)
+            log(this.vm(), FeesWithdrawn {
+                token,
+                recipient,
+                amount,
+            });
+
+            Ok(amount)
+        })
     }
 
-    /// Execute transfer using the best route (mock implementation)
+    /// Set the highest `integrator_fee_bps` an execution may pass, at most 3%
+    /// (timelocked)
     #[allow(non_snake_case)]
-    pub fn execute_best_route(
-        &mut self,
-        _to_chain: U256,
-        _token: Address,
-        amount: U256,
-        recipient: Address,
-    ) -> Result<bool, Vec<u8>> {
-        if amount == U256::ZERO {
-            return Err(b"Invalid amount".to_vec());
-        }
-        if recipient == Address::ZERO {
-            return Err(b"Invalid recipient".to_vec());
+    pub fn set_max_integrator_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_max_integrator_fee(fee_bps)
+        })
+    }
+
+    /// Get the highest integrator fee in basis points
+    #[allow(non_snake_case)]
+    pub fn get_max_integrator_fee(&self) -> U256 {
+        self.max_integrator_fee_bps.get()
+    }
+
+    /// Get the fees `integrator` has accrued in `token` and not yet claimed
+    #[allow(non_snake_case)]
+    pub fn get_integrator_fees(&self, integrator: Address, token: Address) -> U256 {
+        self.integrator_fees.getter(integrator).get(token)
+    }
+
+    /// Get an integrator's stats: (transfers, volume_usd). USD has 8 decimals.
+    #[allow(non_snake_case)]
+    pub fn get_integrator_stats(&self, integrator: Address) -> (U256, U256) {
+        (
+            self.integrator_transfers.get(integrator),
+            self.integrator_volume_usd.get(integrator),
+        )
+    }
+
+    /// Claim the caller's integrator fees in `token`
+    #[allow(non_snake_case)]
+    pub fn claim_integrator_fees(&mut self, token: Address) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            let integrator = this.vm().msg_sender();
+            let amount = this.integrator_fees.getter(integrator).get(token);
+            if amount == U256::ZERO {
+                return Err(InvalidAmount {}.into());
+            }
+
+            this.integrator_fees.setter(integrator).setter(token).set(U256::ZERO);
+            let liabilities = this.integrator_fee_liabilities.get(token);
+            this.integrator_fee_liabilities.setter(token).set(liabilities - amount);
+            this.send_tokens(token, integrator, amount)?;
+
+            log(this.vm(), IntegratorFeesClaimed {
+                integrator,
+                token,
+                amount,
+            });
+
+            Ok(amount)
+        })
+    }
+
+    /// Set how many routes `get_routes` returns
+    #[allow(non_snake_case)]
+    pub fn set_max_routes(&mut self, max_routes: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            if max_routes == U256::ZERO {
+                return Err(InvalidMaxRoutes {}.into());
+            }
+
+            let previous = this.get_max_routes();
+            this.max_routes.set(max_routes);
+
+            log(this.vm(), MaxRoutesUpdated {
+                previousValue: previous,
+                newValue: max_routes,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get how many routes `get_routes` returns
+    #[allow(non_snake_case)]
+    pub fn get_max_routes(&self) -> U256 {
+        let max_routes = self.max_routes.get();
+        if max_routes == U256::ZERO {
+            U256::from(DEFAULT_MAX_ROUTES)
+        } else {
+            max_routes
         }
-        if self.adapter_count.get() == U256::ZERO {
-            return Err(b"No adapters available".to_vec());
+    }
+
+    /// Configure split quotes: at most `max_legs` adapters, allocated in
+    /// `increment_bps` slices (must divide 10_000)
+    #[allow(non_snake_case)]
+    pub fn set_split_config(
+        &mut self,
+        max_legs: U256,
+        increment_bps: U256,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            let max_bps = U256::from(MAX_BPS);
+            if max_legs == U256::ZERO
+                || increment_bps == U256::ZERO
+                || increment_bps > max_bps
+                || max_bps % increment_bps != U256::ZERO
+            {
+                return Err(InvalidSplitConfig {
+                    maxLegs: max_legs,
+                    incrementBps: increment_bps,
+                }
+                .into());
+            }
+
+            this.max_split_legs.set(max_legs);
+            this.split_increment_bps.set(increment_bps);
+
+            log(this.vm(), SplitConfigUpdated {
+                maxLegs: max_legs,
+                incrementBps: increment_bps,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get the split configuration: (max_legs, increment_bps)
+    #[allow(non_snake_case)]
+    pub fn get_split_config(&self) -> (U256, U256) {
+        if self.max_split_legs.get() == U256::ZERO {
+            return (
+                U256::from(DEFAULT_MAX_SPLIT_LEGS),
+                U256::from(DEFAULT_SPLIT_INCREMENT_BPS),
+            );
         }
+        (self.max_split_legs.get(), self.split_increment_bps.get())
+    }
 
-        // Update statistics
-        let current_transfers = self.total_transfers.get();
-        self.total_transfers.set(current_transfers + U256::from(1));
+    /// Stop all routing. Pausers can pause; only the owner can unpause.
+    #[allow(non_snake_case)]
+    pub fn pause(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(PAUSER_ROLE)?;
+            this.paused.set(true);
+            log(this.vm(), Paused {
+                account: this.vm().msg_sender(),
+            });
+            Ok(())
+        })
+    }
 
-        Ok(true)
+    /// Resume routing after `pause`
+    #[allow(non_snake_case)]
+    pub fn unpause(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.paused.set(false);
+            log(this.vm(), Unpaused {
+                account: this.vm().msg_sender(),
+            });
+            Ok(())
+        })
     }
 
-    /// Get total number of transfers
+    /// Stop or resume routing through `adapter`
     #[allow(non_snake_case)]
-    pub fn get_total_transfers(&self) -> U256 {
-        self.total_transfers.get()
+    pub fn set_adapter_paused(
+        &mut self,
+        adapter: Address,
+        paused: bool,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_adapters.insert(adapter, paused);
+            log(this.vm(), AdapterPauseUpdated {
+                adapter,
+                paused,
+                account: this.vm().msg_sender(),
+            });
+            Ok(())
+        })
     }
 
-    /// Get adapter count
+    /// Stop or resume routing to `chain_id`
     #[allow(non_snake_case)]
-    pub fn get_adapter_count(&self) -> U256 {
-        self.adapter_count.get()
+    pub fn set_chain_paused(
+        &mut self,
+        chain_id: U256,
+        paused: bool,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_chains.insert(chain_id, paused);
+            log(this.vm(), ChainPauseUpdated {
+                chainId: chain_id,
+                paused,
+                account: this.vm().msg_sender(),
+            });
+            Ok(())
+        })
     }
 
-    // Internal helper
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        if msg::sender() != self.owner.get() {
-            return Err(b"Not owner".to_vec());
-        }
-        Ok(())
+    /// Stop or resume routing `token`
+    #[allow(non_snake_case)]
+    pub fn set_token_paused(
+        &mut self,
+        token: Address,
+        paused: bool,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_tokens.insert(token, paused);
+            log(this.vm(), TokenPauseUpdated {
+                token,
+                paused,
+                account: this.vm().msg_sender(),
+            });
+            Ok(())
+        })
     }
-}
 
-#[cfg(not(any(test, feature = "export-abi")))]
-#[panic_handler]
-fn panic(_info: &core::panic::PanicInfo) -> ! {
-    loop {}
-}
+    /// Check whether all routing is paused
+    #[allow(non_snake_case)]
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Check whether `adapter` is paused
+    #[allow(non_snake_case)]
+    pub fn is_adapter_paused(&self, adapter: Address) -> bool {
+        self.paused_adapters.get(adapter)
+    }
 
-    #[test]
-    fn test_settlement_switch_initialization() {
-        // Basic test structure - full testing would require mock contracts
-        let oracle = Address::from([1u8; 20]);
-        assert!(oracle != Address::ZERO);
+    /// Check whether routing to `chain_id` is paused
+    #[allow(non_snake_case)]
+    pub fn is_chain_paused(&self, chain_id: U256) -> bool {
+        self.paused_chains.get(chain_id)
     }
-}
 
+    /// Check whether `token` is paused
+    #[allow(non_snake_case)]
+    pub fn is_token_paused(&self, token: Address) -> bool {
+        self.paused_tokens.get(token)
+    }
+
+    /// Propose a new owner (timelocked); see `Ownable::transfer_ownership`
+    #[allow(non_snake_case)]
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.ownable.transfer_ownership(new_owner)
+        })
+    }
+
+    /// Become the owner, taking over the previous owner's admin role; see
+    /// `Ownable::accept_ownership`
+    #[allow(non_snake_case)]
+    pub fn accept_ownership(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            let previous = this.ownable.get_owner();
+            this.ownable.accept_ownership()?;
+            let owner = this.ownable.get_owner();
+            this.access_control.transfer_admin(previous, owner);
+            Ok(())
+        })
+    }
+
+    /// Give up ownership and the owner's admin role for good (timelocked)
+    #[allow(non_snake_case)]
+    pub fn renounce_ownership(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_renounce_ownership()
+        })
+    }
+
+    /// Grant `role` to `account`; see `AccessControl::grant_role`
+    #[allow(non_snake_case)]
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| this.access_control.grant_role(role, account))
+    }
+
+    /// Revoke `role` from `account`; see `AccessControl::revoke_role`
+    #[allow(non_snake_case)]
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| this.access_control.revoke_role(role, account))
+    }
+
+    /// Give up `role` held by the caller; see `AccessControl::renounce_role`
+    #[allow(non_snake_case)]
+    pub fn renounce_role(&mut self, role: B256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| this.access_control.renounce_role(role))
+    }
+
+    /// Set the timelock delay in seconds, at most 30 days. Turning the timelock
+    /// on is instant; once on, changing the delay is itself queued.
+    #[allow(non_snake_case)]
+    pub fn set_timelock_delay(&mut self, delay: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_timelock_delay(delay)
+        })
+    }
+
+    /// Get the timelock delay in seconds; zero means the timelock is off
+    #[allow(non_snake_case)]
+    pub fn get_timelock_delay(&self) -> U256 {
+        self.timelock_delay.get()
+    }
+
+    /// Queue a timelocked change of `kind` (an `OP_*` constant) to `value`. Only
+    /// whoever may make the change directly can queue it. While the timelock is
+    /// on, adapter additions, oracle, fee and ownership changes must go through
+    /// the queue; pausing and removing adapters stay instant. Returns the
+    /// operation id.
+    #[allow(non_snake_case)]
+    pub fn queue_operation(&mut self, kind: u8, value: U256) -> Result<B256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_operation_auth(kind)?;
+            let operation_id = this.operation_id(kind, value);
+            if this.operation_etas.get(operation_id) != U256::ZERO {
+                return Err(OperationAlreadyQueued {
+                    operationId: operation_id,
+                }
+                .into());
+            }
+
+            let now = U256::from(this.vm().block_timestamp());
+            let eta = now + this.timelock_delay.get();
+            this.operation_etas.insert(operation_id, eta);
+
+            log(this.vm(), OperationQueued {
+                operationId: operation_id,
+                kind,
+                value,
+                eta,
+            });
+
+            Ok(operation_id)
+        })
+    }
+
+    /// Apply a queued change once its delay has passed
+    #[allow(non_snake_case)]
+    pub fn execute_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_operation_auth(kind)?;
+            let operation_id = this.operation_id(kind, value);
+            let eta = this.operation_etas.get(operation_id);
+            if eta == U256::ZERO {
+                return Err(OperationNotQueued {
+                    operationId: operation_id,
+                }
+                .into());
+            }
+            if U256::from(this.vm().block_timestamp()) < eta {
+                return Err(OperationNotReady {
+                    operationId: operation_id,
+                    eta,
+                }
+                .into());
+            }
+
+            this.operation_etas.insert(operation_id, U256::ZERO);
+            this.apply_operation(kind, value)?;
+
+            log(this.vm(), OperationExecuted {
+                operationId: operation_id,
+                kind,
+                value,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Drop a queued change. Pausers can cancel any operation, so a queued
+    /// change from a compromised key can be stopped.
+    #[allow(non_snake_case)]
+    pub fn cancel_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            if this.ensure_role(PAUSER_ROLE).is_err() {
+                this.ensure_operation_auth(kind)?;
+            }
+            let operation_id = this.operation_id(kind, value);
+            if this.operation_etas.get(operation_id) == U256::ZERO {
+                return Err(OperationNotQueued {
+                    operationId: operation_id,
+                }
+                .into());
+            }
+
+            this.operation_etas.insert(operation_id, U256::ZERO);
+
+            log(this.vm(), OperationCancelled {
+                operationId: operation_id,
+                kind,
+                value,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get when a queued operation becomes executable; zero if not queued
+    #[allow(non_snake_case)]
+    pub fn get_operation_eta(&self, kind: u8, value: U256) -> U256 {
+        self.operation_etas.get(self.operation_id(kind, value))
+    }
+
+    /// Set `token`'s rate limits: the largest single transfer, rolling hourly and
+    /// daily volume, and rolling daily volume per sender. Zero lifts a limit.
+    #[allow(non_snake_case)]
+    pub fn set_rate_limits(
+        &mut self,
+        token: Address,
+        max_transfer: U256,
+        hourly_limit: U256,
+        daily_limit: U256,
+        user_daily_limit: U256,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            if token == Address::ZERO {
+                return Err(InvalidToken {}.into());
+            }
+
+            this.max_transfer_amounts.setter(token).set(max_transfer);
+            this.hourly_limits.setter(token).set(hourly_limit);
+            this.daily_limits.setter(token).set(daily_limit);
+            this.user_daily_limits.setter(token).set(user_daily_limit);
+
+            log(this.vm(), RateLimitsUpdated {
+                token,
+                maxTransfer: max_transfer,
+                hourlyLimit: hourly_limit,
+                dailyLimit: daily_limit,
+                userDailyLimit: user_daily_limit,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get `token`'s rate limits: (max_transfer, hourly, daily, user_daily)
+    #[allow(non_snake_case)]
+    pub fn get_rate_limits(&self, token: Address) -> (U256, U256, U256, U256) {
+        (
+            self.max_transfer_amounts.get(token),
+            self.hourly_limits.get(token),
+            self.daily_limits.get(token),
+            self.user_daily_limits.get(token),
+        )
+    }
+
+    /// Get what each of `token`'s rate limits still allows `user` to send now:
+    /// (max_transfer, hourly, daily, user_daily). Unlimited ones read `U256::MAX`.
+    #[allow(non_snake_case)]
+    pub fn get_rate_limit_capacity(
+        &self,
+        token: Address,
+        user: Address,
+    ) -> (U256, U256, U256, U256) {
+        self.rate_limit_capacity(token, user)
+    }
+
+    /// Get the largest amount of `token` that `user` can send now
+    #[allow(non_snake_case)]
+    pub fn get_transfer_capacity(&self, token: Address, user: Address) -> U256 {
+        let (max_transfer, hourly, daily, user_daily) = self.rate_limit_capacity(token, user);
+        max_transfer.min(hourly).min(daily).min(user_daily)
+    }
+
+    /// Cap the USD volume (8 decimals) in flight through `adapter`; zero lifts
+    /// the cap. Routes that would exceed it are unavailable.
+    #[allow(non_snake_case)]
+    pub fn set_adapter_exposure_cap(
+        &mut self,
+        adapter: Address,
+        cap_usd: U256,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            if adapter == Address::ZERO {
+                return Err(InvalidAdapter { adapter }.into());
+            }
+            this.adapter_exposure_caps.setter(adapter).set(cap_usd);
+            log(this.vm(), AdapterExposureCapUpdated {
+                adapter,
+                capUsd: cap_usd,
+            });
+            Ok(())
+        })
+    }
+
+    /// Get the USD volume in flight through `adapter` and its cap: (exposure, cap)
+    #[allow(non_snake_case)]
+    pub fn get_adapter_exposure(&self, adapter: Address) -> (U256, U256) {
+        (self.adapter_exposure.get(adapter), self.adapter_exposure_caps.get(adapter))
+    }
+
+    /// Get `adapter`'s reported outcomes: (successes, failures, recent_failures)
+    #[allow(non_snake_case)]
+    pub fn get_adapter_health(&self, adapter: Address) -> (U256, U256, U256) {
+        (
+            self.adapter_successes.get(adapter),
+            self.adapter_failures.get(adapter),
+            self.recent_failures(adapter),
+        )
+    }
+
+    /// Report how a pending transfer ended. Settles its volume out of the
+    /// adapter's exposure and counts the outcome toward the adapter's health.
+    /// Only relayers can report.
+    #[allow(non_snake_case)]
+    pub fn report_transfer_status(
+        &mut self,
+        transfer_id: B256,
+        status: u8,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(RELAYER_ROLE)?;
+            let pending = this.get_transfer_status(transfer_id) == TRANSFER_PENDING;
+            if !pending || (status != TRANSFER_COMPLETED && status != TRANSFER_FAILED) {
+                return Err(InvalidTransferStatus {
+                    transferId: transfer_id,
+                    status,
+                }
+                .into());
+            }
+
+            let adapter = this.transfer_adapters.get(transfer_id);
+            let volume_usd = this.transfer_volumes_usd.get(transfer_id);
+            let exposure = this.adapter_exposure.get(adapter);
+            this.adapter_exposure.setter(adapter).set(exposure.saturating_sub(volume_usd));
+            this.transfer_volumes_usd.setter(transfer_id).set(U256::ZERO);
+            this.transfer_statuses.setter(transfer_id).set(U8::from(status));
+
+            if status == TRANSFER_COMPLETED {
+                let successes = this.adapter_successes.get(adapter);
+                this.adapter_successes.setter(adapter).set(successes + U256::from(1));
+            } else {
+                let failures = this.adapter_failures.get(adapter);
+                this.adapter_failures.setter(adapter).set(failures + U256::from(1));
+                let recent = this.recent_failures(adapter);
+                this.adapter_recent_failures.setter(adapter).set(recent + U256::from(1));
+                let now = U256::from(this.vm().block_timestamp());
+                this.adapter_last_failure.setter(adapter).set(now);
+            }
+
+            log(this.vm(), TransferStatusUpdated {
+                transferId: transfer_id,
+                adapter,
+                status,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get a transfer's status: pending, completed or failed; zero if unknown
+    #[allow(non_snake_case)]
+    pub fn get_transfer_status(&self, transfer_id: B256) -> u8 {
+        self.transfer_statuses.get(transfer_id).to()
+    }
+
+    /// Set the sanctions oracle (Chainalysis `isSanctioned`) screening senders and
+    /// recipients; zero turns it off. The denylist applies either way.
+    #[allow(non_snake_case)]
+    pub fn set_screening_oracle(&mut self, oracle: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_screening_oracle(oracle)
+        })
+    }
+
+    /// Get the sanctions oracle; zero when off
+    #[allow(non_snake_case)]
+    pub fn get_screening_oracle(&self) -> Address {
+        self.screening_oracle.get()
+    }
+
+    /// Add `account` to or remove it from the denylist
+    #[allow(non_snake_case)]
+    pub fn set_denied(&mut self, account: Address, denied: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.denylist.setter(account).set(denied);
+            log(this.vm(), DenylistUpdated { account, denied });
+            Ok(())
+        })
+    }
+
+    /// Check whether `account` is on the denylist
+    #[allow(non_snake_case)]
+    pub fn is_denied(&self, account: Address) -> bool {
+        self.denylist.get(account)
+    }
+
+    /// Point the ERC-1967 proxy at `new_implementation` (timelocked). Only admins
+    /// can upgrade; call `migrate` afterwards if the new code bumps the layout.
+    #[allow(non_snake_case)]
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADMIN_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_upgrade(new_implementation)
+        })
+    }
+
+    /// ERC-1822: the storage slot this implementation expects the proxy to use
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> B256 {
+        erc1967::IMPLEMENTATION_SLOT
+    }
+
+    /// Get the implementation the proxy delegates to; zero outside a proxy
+    #[allow(non_snake_case)]
+    pub fn get_implementation(&self) -> Address {
+        erc1967::implementation(self.vm())
+    }
+
+    /// Get the layout version the storage was last migrated to
+    #[allow(non_snake_case)]
+    pub fn get_storage_version(&self) -> U256 {
+        self.storage_version.get()
+    }
+
+    /// Run the migrations from the stored layout version up to
+    /// `STORAGE_VERSION`. Only admins can migrate; nothing happens when the
+    /// storage is current.
+    #[allow(non_snake_case)]
+    pub fn migrate(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADMIN_ROLE)?;
+            let from = this.storage_version.get();
+            let current = U256::from(STORAGE_VERSION);
+            if from > current {
+                return Err(InvalidStorageVersion {
+                    version: from,
+                    currentVersion: current,
+                }
+                .into());
+            }
+            if from == current {
+                return Ok(());
+            }
+
+            for version in from.to::<u64>()..STORAGE_VERSION {
+                this.migrate_from(version);
+            }
+            this.storage_version.set(current);
+
+            log(this.vm(), StorageMigrated {
+                fromVersion: from,
+                toVersion: current,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Get up to `max_routes` routes, best first under `speed_weight_bps`. With a
+    /// `native_drop_amount` (destination wei), its USD cost is part of every route.
+    /// Adapters that cannot deliver the drop, or a payload when `with_payload` is
+    /// set, come last, marked unavailable, as do paused routes. `integrator_fee_bps`
+    /// is the cut an integrator will take on execution, quoted next to the protocol fee.
+    /// Fails for a blocked caller or `recipient`.
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_routes(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        speed_weight_bps: U256,
+        native_drop_amount: U256,
+        with_payload: bool,
+        integrator_fee_bps: U256,
+    ) -> Result<Vec<RouteQuote>, SettlementError> {
+        self.ensure_not_blocked(&[self.vm().msg_sender(), recipient])?;
+        self.ensure_token_supported(token)?;
+        if amount == U256::ZERO {
+            return Err(InvalidAmount {}.into());
+        }
+        self.ensure_integrator_fee(integrator_fee_bps)?;
+
+        let options = RouteOptions {
+            native_drop: native_drop_amount,
+            with_payload,
+            integrator_fee_bps,
+        };
+        let routes =
+            self.rank_routes(from_chain, to_chain, token, amount, speed_weight_bps, options)?;
+        Ok(routes
+            .into_iter()
+            .take(self.get_max_routes().saturating_to())
+            .map(PricedRoute::into_quote)
+            .collect())
+    }
+
+    /// Get the best route for a transfer. `speed_weight_bps` trades total USD cost
+    /// (0) against value of time * estimated_time (10_000), like the UI slider.
+    /// Fails for a blocked caller or `recipient`.
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_best_route(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        speed_weight_bps: U256,
+        native_drop_amount: U256,
+        with_payload: bool,
+        integrator_fee_bps: U256,
+    ) -> Result<RouteQuote, SettlementError> {
+        self.ensure_not_blocked(&[self.vm().msg_sender(), recipient])?;
+        self.ensure_token_supported(token)?;
+        if amount == U256::ZERO {
+            return Err(InvalidAmount {}.into());
+        }
+        self.ensure_integrator_fee(integrator_fee_bps)?;
+
+        let options = RouteOptions {
+            native_drop: native_drop_amount,
+            with_payload,
+            integrator_fee_bps,
+        };
+        let route =
+            self.find_best_route(from_chain, to_chain, token, amount, speed_weight_bps, options)?;
+        Ok(route.into_quote())
+    }
+
+    /// Execute transfer using the best route for `speed_weight_bps`. Reverts if the
+    /// recipient would get less than `min_amount_out` or `deadline` has passed.
+    /// A non-zero `native_drop_amount` is delivered to the recipient as destination
+    /// gas and paid for with at least `get_native_drop_value` of extra `msg.value`.
+    /// A non-empty `payload` is delivered with the funds to `recipient` as a
+    /// contract call, so only adapters that support payloads are used.
+    /// `integrator` earns `integrator_fee_bps` of the amount, up to the owner's maximum.
+    #[payable]
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_best_route(
+        &mut self,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        speed_weight_bps: U256,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        payload: Bytes,
+        integrator: Address,
+        integrator_fee_bps: U256,
+    ) -> Result<bool, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(deadline)?;
+            let mut transfer = Transfer {
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop: native_drop_amount,
+                native_drop_value: U256::ZERO,
+                payload: payload.0,
+                integrator,
+                integrator_fee_bps,
+            };
+            this.ensure_valid_transfer(&transfer)?;
+
+            transfer.native_drop_value = this.collect_payment(&transfer)?;
+            this.route_and_dispatch(&transfer, speed_weight_bps)?;
+
+            Ok(true)
+        })
+    }
+
+    /// Execute transfer through a route picked from `get_routes`. The adapter is
+    /// re-quoted and the call reverts if the quote fell below `min_amount_out` or
+    /// `deadline` has passed. `native_drop_amount`, `payload` and the integrator
+    /// fee work as in `execute_best_route`.
+    #[payable]
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_with_adapter(
+        &mut self,
+        adapter: Address,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        native_drop_amount: U256,
+        payload: Bytes,
+        integrator: Address,
+        integrator_fee_bps: U256,
+    ) -> Result<B256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(deadline)?;
+            let mut transfer = Transfer {
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop: native_drop_amount,
+                native_drop_value: U256::ZERO,
+                payload: payload.0,
+                integrator,
+                integrator_fee_bps,
+            };
+            this.ensure_valid_transfer(&transfer)?;
+            if !this.is_bridge_adapter.get(adapter) {
+                return Err(AdapterNotFound { adapter }.into());
+            }
+            if this.paused_adapters.get(adapter) {
+                return Err(AdapterPaused { adapter }.into());
+            }
+            if native_drop_amount > U256::ZERO && !this.supports_native_drop(adapter) {
+                return Err(NativeDropNotSupported { adapter }.into());
+            }
+            if !transfer.payload.is_empty() && !this.supports_payload(adapter) {
+                return Err(PayloadNotSupported { adapter }.into());
+            }
+
+            let from_chain = U256::from(this.vm().chain_id());
+            let pricing = this.token_pricing(from_chain, token)?;
+            let route = this
+                .price_route(
+                    adapter,
+                    from_chain,
+                    to_chain,
+                    token,
+                    amount,
+                    pricing,
+                    integrator_fee_bps,
+                )?
+                .ok_or(NoRouteAvailable {
+                    toChain: to_chain,
+                    token,
+                })?;
+            if route.amount_out < min_amount_out {
+                return Err(InsufficientOutput {
+                    amountOut: route.amount_out,
+                    minAmountOut: min_amount_out,
+                }
+                .into());
+            }
+
+            transfer.native_drop_value = this.collect_payment(&transfer)?;
+            this.dispatch(adapter, &transfer)
+        })
+    }
+
+    /// Source-chain native value that pays for `native_drop_amount` wei of gas on
+    /// `to_chain`, at oracle prices. Send at least this on top of the transfer.
+    #[allow(non_snake_case)]
+    pub fn get_native_drop_value(
+        &self,
+        to_chain: U256,
+        native_drop_amount: U256,
+    ) -> Result<U256, SettlementError> {
+        let from_chain = U256::from(self.vm().chain_id());
+        Ok(self.native_drop_pricing(from_chain, to_chain, native_drop_amount)?.1)
+    }
+
+    /// Quote `amount` split across adapters to maximise total amount_out, after
+    /// the protocol fee and `integrator_fee_bps`. Fails for a blocked caller.
+    #[allow(non_snake_case)]
+    pub fn get_split_quote(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        integrator_fee_bps: U256,
+    ) -> Result<SplitQuote, SettlementError> {
+        self.ensure_not_blocked(&[self.vm().msg_sender()])?;
+        self.ensure_token_supported(token)?;
+        if amount == U256::ZERO {
+            return Err(InvalidAmount {}.into());
+        }
+        self.ensure_integrator_fee(integrator_fee_bps)?;
+
+        let legs = self.split_legs(from_chain, to_chain, token, amount, integrator_fee_bps)?;
+        let total_out = legs.iter().fold(U256::ZERO, |total, leg| total + leg.2);
+        Ok((
+            legs.iter().map(|leg| leg.0).collect(),
+            legs.iter().map(|leg| leg.1).collect(),
+            legs.iter().map(|leg| leg.2).collect(),
+            total_out,
+        ))
+    }
+
+    /// Execute the split quote. Every leg goes through its adapter in this call;
+    /// if any leg fails the whole transaction reverts. Returns one transfer id per leg.
+    #[payable]
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_split(
+        &mut self,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        recipient: Address,
+        min_amount_out: U256,
+        deadline: U256,
+        integrator: Address,
+        integrator_fee_bps: U256,
+    ) -> Result<Vec<B256>, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(deadline)?;
+            let transfer = Transfer {
+                to_chain,
+                token,
+                amount,
+                recipient,
+                min_amount_out,
+                deadline,
+                native_drop: U256::ZERO,
+                native_drop_value: U256::ZERO,
+                payload: Vec::new(),
+                integrator,
+                integrator_fee_bps,
+            };
+            this.ensure_valid_transfer(&transfer)?;
+
+            let from_chain = U256::from(this.vm().chain_id());
+            let legs = this.split_legs(from_chain, to_chain, token, amount, integrator_fee_bps)?;
+            let total_out = legs.iter().fold(U256::ZERO, |total, leg| total + leg.2);
+            if total_out < min_amount_out {
+                return Err(InsufficientOutput {
+                    amountOut: total_out,
+                    minAmountOut: min_amount_out,
+                }
+                .into());
+            }
+
+            this.collect_payment(&transfer)?;
+
+            let mut transfer_ids = Vec::new();
+            for (adapter, leg_amount, leg_out) in legs {
+                // Each leg must deliver its share of the user's minimum
+                let leg_min = leg_out * min_amount_out / total_out;
+                let transfer_id = this.dispatch(adapter, &Transfer {
+                    to_chain,
+                    token,
+                    amount: leg_amount,
+                    min_amount_out: leg_min,
+                    payload: Vec::new(),
+                    ..transfer
+                })?;
+                transfer_ids.push(transfer_id);
+            }
+
+            Ok(transfer_ids)
+        })
+    }
+
+    /// Send many transfers in one call. Each token's total is pulled once and
+    /// every leg is routed on its own. With `skip_failed`, legs that cannot be
+    /// routed or executed are refunded to the sender and get a zero transfer id;
+    /// otherwise any failing leg reverts the batch. The integrator fee applies to
+    /// every leg.
+    #[payable]
+    #[allow(non_snake_case)]
+    pub fn execute_batch(
+        &mut self,
+        legs: Vec<BatchLeg>,
+        speed_weight_bps: U256,
+        deadline: U256,
+        skip_failed: bool,
+        integrator: Address,
+        integrator_fee_bps: U256,
+    ) -> Result<Vec<B256>, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(deadline)?;
+            if legs.is_empty() {
+                return Err(InvalidAmount {}.into());
+            }
+
+            let transfers: Vec<Transfer> = legs
+                .into_iter()
+                .map(|(to_chain, token, amount, recipient, min_amount_out)| Transfer {
+                    to_chain,
+                    token,
+                    amount,
+                    recipient,
+                    min_amount_out,
+                    deadline,
+                    native_drop: U256::ZERO,
+                    native_drop_value: U256::ZERO,
+                    payload: Vec::new(),
+                    integrator,
+                    integrator_fee_bps,
+                })
+                .collect();
+
+            // Validate every leg and total the amounts per token
+            let mut totals: Vec<(Address, U256)> = Vec::new();
+            for transfer in &transfers {
+                this.ensure_valid_transfer(transfer)?;
+                match totals.iter_mut().find(|(token, _)| *token == transfer.token) {
+                    Some((_, total)) => *total += transfer.amount,
+                    None => totals.push((transfer.token, transfer.amount)),
+                }
+            }
+            let native_total = totals
+                .iter()
+                .find(|(token, _)| *token == NATIVE_TOKEN)
+                .map_or(U256::ZERO, |(_, total)| *total);
+            this.ensure_msg_value(native_total)?;
+            for (token, total) in totals {
+                if token != NATIVE_TOKEN {
+                    this.pull_tokens(token, total)?;
+                }
+            }
+
+            let sender = this.vm().msg_sender();
+            let mut transfer_ids = Vec::new();
+            for (index, transfer) in transfers.iter().enumerate() {
+                match this.route_and_dispatch(transfer, speed_weight_bps) {
+                    Ok(transfer_id) => transfer_ids.push(transfer_id),
+                    Err(err) if !skip_failed => return Err(err),
+                    Err(_) => {
+                        this.send_tokens(transfer.token, sender, transfer.amount)?;
+                        log(this.vm(), BatchLegRefunded {
+                            index: U256::from(index),
+                            sender,
+                            token: transfer.token,
+                            amount: transfer.amount,
+                            recipient: transfer.recipient,
+                        });
+                        transfer_ids.push(B256::ZERO);
+                    }
+                }
+            }
+
+            Ok(transfer_ids)
+        })
+    }
+
+    /// Credit a refund a bridge pushed back to the router (Across expired deposits,
+    /// failed Stargate swaps) to `transfer_id`, for its sender to claim. `token` is
+    /// what the bridge returned, e.g. WETH for a wrapped native transfer.
+    #[allow(non_snake_case)]
+    pub fn process_refund(
+        &mut self,
+        transfer_id: B256,
+        token: Address,
+        amount: U256,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            if this.transfer_senders.get(transfer_id) == Address::ZERO {
+                return Err(TransferNotFound {
+                    transferId: transfer_id,
+                }
+                .into());
+            }
+            if amount == U256::ZERO {
+                return Err(InvalidAmount {}.into());
+            }
+            let pending = this.refund_amounts.get(transfer_id);
+            let pending_token = this.refund_tokens.get(transfer_id);
+            if pending > U256::ZERO && pending_token != token {
+                return Err(RefundTokenMismatch {
+                    pendingToken: pending_token,
+                    token,
+                }
+                .into());
+            }
+
+            // Only credit funds the router actually holds beyond earlier refunds and fees
+            let liabilities = this.refund_liabilities.get(token) + amount;
+            let fees = this.accrued_fees.get(token) + this.integrator_fee_liabilities.get(token);
+            let required = liabilities + fees;
+            let balance = this.token_balance(token)?;
+            if balance < required {
+                return Err(InsufficientRefundBalance {
+                    token,
+                    balance,
+                    required,
+                }
+                .into());
+            }
+
+            this.refund_liabilities.setter(token).set(liabilities);
+            this.refund_tokens.setter(transfer_id).set(token);
+            this.refund_amounts.setter(transfer_id).set(pending + amount);
+
+            log(this.vm(), RefundProcessed {
+                transferId: transfer_id,
+                token,
+                amount,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Claim the refund credited to `transfer_id`. Only the transfer's original
+    /// sender can claim; the funds go to the caller.
+    #[allow(non_snake_case)]
+    pub fn claim_refund(&mut self, transfer_id: B256) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            let sender = this.vm().msg_sender();
+            this.ensure_not_blocked(&[sender])?;
+            if this.transfer_senders.get(transfer_id) != sender {
+                return Err(NotTransferSender {
+                    transferId: transfer_id,
+                    caller: sender,
+                }
+                .into());
+            }
+            let amount = this.refund_amounts.get(transfer_id);
+            if amount == U256::ZERO {
+                return Err(NoRefundAvailable {
+                    transferId: transfer_id,
+                }
+                .into());
+            }
+
+            let token = this.refund_tokens.get(transfer_id);
+            this.refund_amounts.setter(transfer_id).set(U256::ZERO);
+            let liabilities = this.refund_liabilities.get(token);
+            this.refund_liabilities.setter(token).set(liabilities - amount);
+            this.send_tokens(token, sender, amount)?;
+
+            log(this.vm(), RefundClaimed {
+                transferId: transfer_id,
+                sender,
+                token,
+                amount,
+            });
+
+            Ok(amount)
+        })
+    }
+
+    /// Get a dispatched transfer: (sender, token, amount). Zero if unknown.
+    #[allow(non_snake_case)]
+    pub fn get_transfer(&self, transfer_id: B256) -> (Address, Address, U256) {
+        (
+            self.transfer_senders.get(transfer_id),
+            self.transfer_tokens.get(transfer_id),
+            self.transfer_amounts.get(transfer_id),
+        )
+    }
+
+    /// Get the unclaimed refund for a transfer: (token, amount)
+    #[allow(non_snake_case)]
+    pub fn get_refund(&self, transfer_id: B256) -> (Address, U256) {
+        (
+            self.refund_tokens.get(transfer_id),
+            self.refund_amounts.get(transfer_id),
+        )
+    }
+
+    /// Get total number of transfers
+    #[allow(non_snake_case)]
+    pub fn get_total_transfers(&self) -> U256 {
+        self.total_transfers.get()
+    }
+
+    /// Get the USD value (8 decimals) of all dispatched transfers at send time
+    #[allow(non_snake_case)]
+    pub fn get_total_volume_usd(&self) -> U256 {
+        self.total_volume_usd.get()
+    }
+
+    /// Get adapter count
+    #[allow(non_snake_case)]
+    pub fn get_adapter_count(&self) -> U256 {
+        self.adapter_count.get()
+    }
+
+    /// Get all registered bridge adapters
+    #[allow(non_snake_case)]
+    pub fn get_bridge_adapters(&self) -> Vec<Address> {
+        (0..self.bridge_adapters.len())
+            .filter_map(|i| self.bridge_adapters.get(i))
+            .collect()
+    }
+
+    /// Check whether a token is on the allowlist
+    #[allow(non_snake_case)]
+    pub fn is_token_supported(&self, token: Address) -> bool {
+        self.supported_tokens.get(token)
+    }
+
+    /// Get all tokens on the allowlist
+    #[allow(non_snake_case)]
+    pub fn get_supported_tokens(&self) -> Vec<Address> {
+        (0..self.token_list.len())
+            .filter_map(|i| self.token_list.get(i))
+            .collect()
+    }
+}
+
+impl NonReentrant for SettlementSwitch {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
+}
+
+impl SettlementSwitch {
+    /// The owner passes every role check
+    fn ensure_role(&self, role: B256) -> Result<(), SettlementError> {
+        if self.ownable.only_owner().is_ok() {
+            return Ok(());
+        }
+        self.access_control.only_role(role)
+    }
+
+    fn apply_price_oracle(&mut self, price_oracle: Address) -> Result<(), SettlementError> {
+        if price_oracle == Address::ZERO {
+            return Err(InvalidOracle {}.into());
+        }
+
+        let previous = self.price_oracle.get();
+        self.price_oracle.set(price_oracle);
+
+        log(self.vm(), PriceOracleUpdated {
+            previousOracle: previous,
+            newOracle: price_oracle,
+        });
+
+        Ok(())
+    }
+
+    fn apply_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        if adapter == Address::ZERO {
+            return Err(InvalidAdapter { adapter }.into());
+        }
+        if self.is_bridge_adapter.get(adapter) {
+            return Err(AdapterExists { adapter }.into());
+        }
+
+        self.bridge_adapters.push(adapter);
+        self.is_bridge_adapter.insert(adapter, true);
+
+        // Increment adapter count
+        let count = self.adapter_count.get();
+        self.adapter_count.set(count + U256::from(1));
+
+        log(self.vm(), BridgeAdapterAdded { adapter });
+
+        Ok(())
+    }
+
+    fn apply_protocol_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        let max_fee_bps = U256::from(MAX_PROTOCOL_FEE_BPS);
+        if fee_bps > max_fee_bps {
+            return Err(FeeTooHigh {
+                feeBps: fee_bps,
+                maxFeeBps: max_fee_bps,
+            }
+            .into());
+        }
+
+        let previous = self.protocol_fee_bps.get();
+        self.protocol_fee_bps.set(fee_bps);
+
+        log(self.vm(), ProtocolFeeUpdated {
+            previousFeeBps: previous,
+            newFeeBps: fee_bps,
+        });
+
+        Ok(())
+    }
+
+    fn apply_fee_recipient(&mut self, recipient: Address) -> Result<(), SettlementError> {
+        if recipient == Address::ZERO {
+            return Err(InvalidAddress {}.into());
+        }
+
+        let previous = self.fee_recipient.get();
+        self.fee_recipient.set(recipient);
+
+        log(self.vm(), FeeRecipientUpdated {
+            previousRecipient: previous,
+            newRecipient: recipient,
+        });
+
+        Ok(())
+    }
+
+    fn apply_max_integrator_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        let ceiling = U256::from(MAX_INTEGRATOR_FEE_BPS);
+        if fee_bps > ceiling {
+            return Err(FeeTooHigh {
+                feeBps: fee_bps,
+                maxFeeBps: ceiling,
+            }
+            .into());
+        }
+
+        let previous = self.max_integrator_fee_bps.get();
+        self.max_integrator_fee_bps.set(fee_bps);
+
+        log(self.vm(), MaxIntegratorFeeUpdated {
+            previousFeeBps: previous,
+            newFeeBps: fee_bps,
+        });
+
+        Ok(())
+    }
+
+    fn apply_timelock_delay(&mut self, delay: U256) -> Result<(), SettlementError> {
+        let max_delay = U256::from(MAX_TIMELOCK_DELAY);
+        if delay > max_delay {
+            return Err(InvalidTimelockDelay {
+                delay,
+                maxDelay: max_delay,
+            }
+            .into());
+        }
+
+        let previous = self.timelock_delay.get();
+        self.timelock_delay.set(delay);
+
+        log(self.vm(), TimelockDelayUpdated {
+            previousDelay: previous,
+            newDelay: delay,
+        });
+
+        Ok(())
+    }
+
+    fn apply_upgrade(&mut self, new_implementation: Address) -> Result<(), SettlementError> {
+        erc1967::set_implementation(self.vm(), new_implementation)?;
+        log(self.vm(), Upgraded {
+            implementation: new_implementation,
+        });
+        Ok(())
+    }
+
+    /// Rewrite storage from layout `version` to `version + 1`
+    fn migrate_from(&mut self, version: u64) {
+        // Version 0 is storage from before versioning, which may only have the
+        // owner set. A set owner means initialized, and proxies initialized before
+        // roles existed have no admin, so the owner becomes the first one. Other
+        // fields added since start at zero, which the getters treat as unset.
+        if version == 0 {
+            self.ownable.migrate_initialized();
+            let owner = self.ownable.get_owner();
+            let admins = self.access_control.get_role_member_count(ADMIN_ROLE);
+            if admins == U256::ZERO && owner != Address::ZERO {
+                self.access_control.init(owner);
+            }
+        }
+    }
+
+    fn apply_screening_oracle(&mut self, oracle: Address) -> Result<(), SettlementError> {
+        let previous = self.screening_oracle.get();
+        self.screening_oracle.set(oracle);
+
+        log(self.vm(), ScreeningOracleUpdated {
+            previousOracle: previous,
+            newOracle: oracle,
+        });
+
+        Ok(())
+    }
+
+    fn apply_weth(&mut self, weth: Address) -> Result<(), SettlementError> {
+        let previous = self.weth.get();
+        self.weth.set(weth);
+
+        log(self.vm(), WethUpdated {
+            previousWeth: previous,
+            newWeth: weth,
+        });
+
+        Ok(())
+    }
+
+    fn apply_renounce_ownership(&mut self) -> Result<(), SettlementError> {
+        let owner = self.ownable.get_owner();
+        self.ownable.renounce_ownership()?;
+        self.access_control.transfer_admin(owner, Address::ZERO);
+        Ok(())
+    }
+
+    /// Sensitive setters only apply directly while the timelock is off
+    fn ensure_timelock_off(&self) -> Result<(), SettlementError> {
+        if self.timelock_delay.get() != U256::ZERO {
+            return Err(TimelockRequired {}.into());
+        }
+        Ok(())
+    }
+
+    /// Operations need the same role as their direct setter; the rest the owner
+    fn ensure_operation_auth(&self, kind: u8) -> Result<(), SettlementError> {
+        match kind {
+            OP_ADD_BRIDGE_ADAPTER => self.ensure_role(ADAPTER_MANAGER_ROLE),
+            OP_SET_PROTOCOL_FEE | OP_SET_MAX_INTEGRATOR_FEE => self.ensure_role(FEE_MANAGER_ROLE),
+            OP_SET_PRICE_ORACLE
+            | OP_SET_FEE_RECIPIENT
+            | OP_TRANSFER_OWNERSHIP
+            | OP_RENOUNCE_OWNERSHIP
+            | OP_SET_TIMELOCK_DELAY
+            | OP_SET_SCREENING_ORACLE
+            | OP_SET_WETH => self.ownable.only_owner(),
+            OP_UPGRADE_TO => self.ensure_role(ADMIN_ROLE),
+            _ => Err(InvalidOperation { kind }.into()),
+        }
+    }
+
+    fn apply_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        let address = Address::from_word(value.into());
+        match kind {
+            OP_ADD_BRIDGE_ADAPTER => self.apply_bridge_adapter(address),
+            OP_SET_PRICE_ORACLE => self.apply_price_oracle(address),
+            OP_SET_PROTOCOL_FEE => self.apply_protocol_fee(value),
+            OP_SET_FEE_RECIPIENT => self.apply_fee_recipient(address),
+            OP_SET_MAX_INTEGRATOR_FEE => self.apply_max_integrator_fee(value),
+            OP_TRANSFER_OWNERSHIP => self.ownable.transfer_ownership(address),
+            OP_RENOUNCE_OWNERSHIP => self.apply_renounce_ownership(),
+            OP_SET_TIMELOCK_DELAY => self.apply_timelock_delay(value),
+            OP_SET_SCREENING_ORACLE => self.apply_screening_oracle(address),
+            OP_UPGRADE_TO => self.apply_upgrade(address),
+            OP_SET_WETH => self.apply_weth(address),
+            _ => Err(InvalidOperation { kind }.into()),
+        }
+    }
+
+    fn operation_id(&self, kind: u8, value: U256) -> B256 {
+        keccak((U256::from(kind), value).abi_encode())
+    }
+
+    /// Pausing needs the pauser role, unpausing the owner
+    fn ensure_pause_change(&self, paused: bool) -> Result<(), SettlementError> {
+        if paused {
+            self.ensure_role(PAUSER_ROLE)
+        } else {
+            self.ownable.only_owner()
+        }
+    }
+
+    /// Reject transfers while routing, `to_chain` or `token` is paused
+    fn ensure_not_paused(&self, to_chain: U256, token: Address) -> Result<(), SettlementError> {
+        if self.paused.get() {
+            return Err(RoutingPaused {}.into());
+        }
+        if self.paused_chains.get(to_chain) {
+            return Err(ChainPaused { chainId: to_chain }.into());
+        }
+        if self.paused_tokens.get(token) {
+            return Err(TokenPaused { token }.into());
+        }
+        Ok(())
+    }
+
+    /// Whether any pause covers sending `token` to `to_chain` through `adapter`
+    fn route_paused(&self, adapter: Address, to_chain: U256, token: Address) -> bool {
+        self.ensure_not_paused(to_chain, token).is_err() || self.paused_adapters.get(adapter)
+    }
+
+    fn ensure_valid_transfer(&self, transfer: &Transfer) -> Result<(), SettlementError> {
+        self.ensure_not_paused(transfer.to_chain, transfer.token)?;
+        self.ensure_not_blocked(&[self.vm().msg_sender(), transfer.recipient])?;
+        self.ensure_token_supported(transfer.token)?;
+        if transfer.amount == U256::ZERO {
+            return Err(InvalidAmount {}.into());
+        }
+        if transfer.recipient == Address::ZERO {
+            return Err(InvalidRecipient {}.into());
+        }
+        self.ensure_integrator_fee(transfer.integrator_fee_bps)?;
+        if transfer.integrator_fee_bps > U256::ZERO && transfer.integrator == Address::ZERO {
+            return Err(InvalidAddress {}.into());
+        }
+        self.ensure_within_limits(transfer.token, self.vm().msg_sender(), transfer.amount)
+    }
+
+    /// Capacity left under each of `token`'s rate limits for `user`:
+    /// (max_transfer, hourly, daily, user_daily), `U256::MAX` where unlimited
+    fn rate_limit_capacity(&self, token: Address, user: Address) -> (U256, U256, U256, U256) {
+        let now = U256::from(self.vm().block_timestamp());
+        let max_transfer = match self.max_transfer_amounts.get(token) {
+            limit if limit == U256::ZERO => U256::MAX,
+            limit => limit,
+        };
+        let hourly = self.hourly_usage.getter(token).remaining(
+            self.hourly_limits.get(token),
+            HOUR,
+            now,
+        );
+        let daily = self.daily_usage.getter(token).remaining(
+            self.daily_limits.get(token),
+            DAY,
+            now,
+        );
+        let user_daily = self.user_daily_usage.getter(token).getter(user).remaining(
+            self.user_daily_limits.get(token),
+            DAY,
+            now,
+        );
+        (max_transfer, hourly, daily, user_daily)
+    }
+
+    /// USD volume `adapter` can still take before reaching its exposure cap;
+    /// `U256::MAX` when uncapped
+    fn exposure_remaining(&self, adapter: Address) -> U256 {
+        let cap = self.adapter_exposure_caps.get(adapter);
+        if cap == U256::ZERO {
+            return U256::MAX;
+        }
+        cap.saturating_sub(self.adapter_exposure.get(adapter))
+    }
+
+    fn ensure_exposure(&self, adapter: Address, volume_usd: U256) -> Result<(), SettlementError> {
+        let remaining = self.exposure_remaining(adapter);
+        if volume_usd > remaining {
+            return Err(ExposureCapExceeded {
+                adapter,
+                volumeUsd: volume_usd,
+                remainingUsd: remaining,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Failures in `adapter`'s current run; zero once a day passes without one
+    fn recent_failures(&self, adapter: Address) -> U256 {
+        let now = U256::from(self.vm().block_timestamp());
+        let since = now.saturating_sub(self.adapter_last_failure.get(adapter));
+        if since > U256::from(DAY) {
+            return U256::ZERO;
+        }
+        self.adapter_recent_failures.get(adapter)
+    }
+
+    /// Raise `score` by `FAILURE_PENALTY_BPS` per recent failure of `adapter`
+    fn failure_penalty(&self, adapter: Address, score: U256) -> U256 {
+        let failures = self.recent_failures(adapter).min(U256::from(MAX_PENALIZED_FAILURES));
+        let penalty_bps = failures * U256::from(FAILURE_PENALTY_BPS);
+        score.saturating_add(score.saturating_mul(penalty_bps) / U256::from(MAX_BPS))
+    }
+
+    /// Reject sending `amount` of `token` for `user` beyond any rate limit
+    fn ensure_within_limits(
+        &self,
+        token: Address,
+        user: Address,
+        amount: U256,
+    ) -> Result<(), SettlementError> {
+        let (max_transfer, hourly, daily, user_daily) = self.rate_limit_capacity(token, user);
+        if amount > max_transfer {
+            return Err(TransferLimitExceeded {
+                token,
+                amount,
+                maxAmount: max_transfer,
+            }
+            .into());
+        }
+        for (window, remaining) in [(HOUR, hourly), (DAY, daily)] {
+            if amount > remaining {
+                return Err(RateLimitExceeded {
+                    token,
+                    window: U256::from(window),
+                    amount,
+                    remaining,
+                }
+                .into());
+            }
+        }
+        if amount > user_daily {
+            return Err(UserRateLimitExceeded {
+                user,
+                token,
+                amount,
+                remaining: user_daily,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Count `amount` of `token` sent by `user` against the rolling limits
+    fn consume_rate_limits(&mut self, token: Address, user: Address, amount: U256) {
+        let now = U256::from(self.vm().block_timestamp());
+        let hourly_limit = self.hourly_limits.get(token);
+        self.hourly_usage.setter(token).consume(amount, hourly_limit, HOUR, now);
+        let daily_limit = self.daily_limits.get(token);
+        self.daily_usage.setter(token).consume(amount, daily_limit, DAY, now);
+        let user_limit = self.user_daily_limits.get(token);
+        let mut usage = self.user_daily_usage.setter(token);
+        usage.setter(user).consume(amount, user_limit, DAY, now);
+    }
+
+    /// Reverse `consume_rate_limits` within the same call
+    fn release_rate_limits(&mut self, token: Address, user: Address, amount: U256) {
+        self.hourly_usage.setter(token).release(amount);
+        self.daily_usage.setter(token).release(amount);
+        self.user_daily_usage.setter(token).setter(user).release(amount);
+    }
+
+    /// Reject `accounts` on the denylist or flagged by the sanctions oracle. A
+    /// reverting oracle blocks everyone.
+    fn ensure_not_blocked(&self, accounts: &[Address]) -> Result<(), SettlementError> {
+        let oracle = self.screening_oracle.get();
+        for &account in accounts {
+            let screen = ISanctionsList::isSanctionedCall { addr: account };
+            let sanctioned = oracle != Address::ZERO
+                && static_call(self.vm(), oracle, screen)
+                    .map_err(|_| ScreeningFailed { oracle, account })?
+                    ._0;
+            if sanctioned || self.denylist.get(account) {
+                return Err(AddressBlocked { account }.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn ensure_integrator_fee(&self, fee_bps: U256) -> Result<(), SettlementError> {
+        let max_fee_bps = self.max_integrator_fee_bps.get();
+        if fee_bps > max_fee_bps {
+            return Err(FeeTooHigh {
+                feeBps: fee_bps,
+                maxFeeBps: max_fee_bps,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn ensure_token_supported(&self, token: Address) -> Result<(), SettlementError> {
+        if !self.supported_tokens.get(token) {
+            return Err(TokenNotSupported { token }.into());
+        }
+        Ok(())
+    }
+
+    /// Pick the available route with the lowest speed-weighted score
+    fn find_best_route(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        speed_weight_bps: U256,
+        options: RouteOptions,
+    ) -> Result<PricedRoute, SettlementError> {
+        let routes =
+            self.rank_routes(from_chain, to_chain, token, amount, speed_weight_bps, options)?;
+        match routes.into_iter().next() {
+            Some(route) if route.available => Ok(route),
+            _ => Err(NoRouteAvailable {
+                toChain: to_chain,
+                token,
+            }
+            .into()),
+        }
+    }
+
+    /// Price every adapter's route and sort by speed-weighted score, lowest first:
+    /// total_cost_usd * (10_000 - w) + value_of_time * estimated_time * w, raised
+    /// for adapters with recent failures. Paused routes, routes over the adapter's
+    /// exposure cap and routes that cannot deliver the native drop or payload in
+    /// `options` sort after all available ones.
+    fn rank_routes(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        speed_weight_bps: U256,
+        options: RouteOptions,
+    ) -> Result<Vec<PricedRoute>, SettlementError> {
+        let max_weight = U256::from(MAX_SPEED_WEIGHT_BPS);
+        if speed_weight_bps > max_weight {
+            return Err(InvalidSpeedWeight {
+                speedWeightBps: speed_weight_bps,
+            }
+            .into());
+        }
+
+        let (token_price, token_unit) = self.token_pricing(from_chain, token)?;
+        let value_of_time = self.get_value_of_time();
+        let native_drop = options.native_drop;
+        let (native_drop_usd, _) = self.native_drop_pricing(from_chain, to_chain, native_drop)?;
+        let volume_usd = amount.saturating_mul(token_price) / token_unit;
+
+        let mut scored: Vec<(U256, PricedRoute)> = Vec::new();
+        for i in 0..self.bridge_adapters.len() {
+            let adapter = self.bridge_adapters.get(i).unwrap();
+            let priced = self.price_route(
+                adapter,
+                from_chain,
+                to_chain,
+                token,
+                amount,
+                (token_price, token_unit),
+                options.integrator_fee_bps,
+            )?;
+            let mut route = match priced {
+                Some(route) => route,
+                None => continue,
+            };
+            if native_drop > U256::ZERO {
+                route.native_drop_usd = native_drop_usd;
+                route.total_cost_usd = route.total_cost_usd.saturating_add(native_drop_usd);
+                route.available = self.supports_native_drop(adapter);
+            }
+            if options.with_payload && route.available {
+                route.available = self.supports_payload(adapter);
+            }
+            if self.route_paused(adapter, to_chain, token)
+                || volume_usd > self.exposure_remaining(adapter)
+            {
+                route.available = false;
+            }
+
+            let cost_score = route.total_cost_usd.saturating_mul(max_weight - speed_weight_bps);
+            let time_score = value_of_time
+                .saturating_mul(route.estimated_time)
+                .saturating_mul(speed_weight_bps);
+            let score = self.failure_penalty(adapter, cost_score.saturating_add(time_score));
+            scored.push((score, route));
+        }
+
+        // Stable sort: ties keep adapter registration order
+        scored.sort_by_key(|(score, route)| (!route.available, *score));
+        Ok(scored.into_iter().map(|(_, route)| route).collect())
+    }
+
+    /// USD price of `token` (8 decimals) and the size of one whole token. The
+    /// native asset is priced as `from_chain`'s native token with 18 decimals.
+    fn token_pricing(
+        &self,
+        from_chain: U256,
+        token: Address,
+    ) -> Result<(U256, U256), SettlementError> {
+        let oracle = self.price_oracle.get();
+        if token == NATIVE_TOKEN {
+            let native = IPriceOracleExternal::getNativeTokenPriceCall { chainId: from_chain };
+            let price = static_call(self.vm(), oracle, native)?._0;
+            return Ok((price, U256::from(10).pow(U256::from(18))));
+        }
+        let token_price =
+            static_call(self.vm(), oracle, IPriceOracleExternal::getTokenPriceCall { token })?._0;
+        let decimals = static_call(self.vm(), token, IERC20Metadata::decimalsCall {})?._0;
+        Ok((token_price, U256::from(10).pow(U256::from(decimals))))
+    }
+
+    /// Quote one adapter for `amount` less the protocol and integrator fees and
+    /// price it in USD at `(token_price, token_unit)`. Adapters that fail to quote
+    /// or report the route unavailable are skipped.
+    #[allow(clippy::too_many_arguments)]
+    fn price_route(
+        &self,
+        adapter: Address,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        (token_price, token_unit): (U256, U256),
+        integrator_fee_bps: U256,
+    ) -> Result<Option<PricedRoute>, SettlementError> {
+        let (protocol_fee, integrator_fee) = self.transfer_fees(amount, integrator_fee_bps);
+        let bridged = amount - protocol_fee - integrator_fee;
+        let route = match self.adapter_quote(adapter, from_chain, to_chain, token, bridged) {
+            Some(route) => route,
+            None => return Ok(None),
+        };
+
+        // Gas is paid on the source chain when the adapter is called
+        let gas_cost_usd = static_call(
+            self.vm(),
+            self.price_oracle.get(),
+            IPriceOracleExternal::calculateGasCostCall {
+                chainId: from_chain,
+                gasAmount: route.estimatedGas,
+            },
+        )?
+        ._0;
+        let fee_usd = route.fee.saturating_mul(token_price) / token_unit;
+        let protocol_fee_usd = protocol_fee.saturating_mul(token_price) / token_unit;
+        let integrator_fee_usd = integrator_fee.saturating_mul(token_price) / token_unit;
+        let total_cost_usd = gas_cost_usd
+            .saturating_add(fee_usd)
+            .saturating_add(protocol_fee_usd)
+            .saturating_add(integrator_fee_usd);
+
+        Ok(Some(PricedRoute {
+            adapter,
+            bridge_name: route.bridgeName,
+            estimated_time: route.estimatedTime,
+            gas_cost_usd,
+            bridge_fee: route.fee,
+            bridge_fee_usd: fee_usd,
+            protocol_fee,
+            protocol_fee_usd,
+            integrator_fee,
+            integrator_fee_usd,
+            native_drop_usd: U256::ZERO,
+            total_cost_usd,
+            amount_out: bridged - route.fee,
+            available: true,
+        }))
+    }
+
+    /// USD cost (8 decimals) of delivering `native_drop` wei on `to_chain`, and the
+    /// `from_chain` native value that pays for it, rounded up
+    fn native_drop_pricing(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        native_drop: U256,
+    ) -> Result<(U256, U256), SettlementError> {
+        if native_drop == U256::ZERO {
+            return Ok((U256::ZERO, U256::ZERO));
+        }
+        let oracle = self.price_oracle.get();
+        let native_price = |chain_id| {
+            let call = IPriceOracleExternal::getNativeTokenPriceCall { chainId: chain_id };
+            static_call(self.vm(), oracle, call).map(|price| price._0)
+        };
+        let destination_price = native_price(to_chain)?;
+        let source_price = native_price(from_chain)?;
+        if source_price == U256::ZERO {
+            return Err(InvalidPriceFeed {}.into());
+        }
+
+        let drop_cost = native_drop.saturating_mul(destination_price);
+        let drop_usd = drop_cost / U256::from(10).pow(U256::from(18));
+        let drop_value = drop_cost.div_ceil(source_price);
+        Ok((drop_usd, drop_value))
+    }
+
+    /// Whether `adapter` can deliver destination gas; adapters without
+    /// `supportsNativeDrop` cannot
+    fn supports_native_drop(&self, adapter: Address) -> bool {
+        let supported =
+            static_call(self.vm(), adapter, IBridgeAdapterExternal::supportsNativeDropCall {});
+        matches!(supported, Ok(ref drop) if drop._0)
+    }
+
+    /// Whether `adapter` can deliver a payload to a destination contract;
+    /// adapters without `supportsPayload` cannot
+    fn supports_payload(&self, adapter: Address) -> bool {
+        let supported =
+            static_call(self.vm(), adapter, IBridgeAdapterExternal::supportsPayloadCall {});
+        matches!(supported, Ok(ref payload) if payload._0)
+    }
+
+    /// Ask an adapter for a route. Failed, unavailable or fee-exceeds-amount
+    /// quotes yield `None`.
+    fn adapter_quote(
+        &self,
+        adapter: Address,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+    ) -> Option<IBridgeAdapterExternal::getRouteReturn> {
+        let quote = IBridgeAdapterExternal::getRouteCall {
+            fromChain: from_chain,
+            toChain: to_chain,
+            token: self.bridged_token(adapter, token)?,
+            amount,
+        };
+        match static_call(self.vm(), adapter, quote) {
+            Ok(route) if route.available && route.fee < amount => Some(route),
+            _ => None,
+        }
+    }
+
+    /// Allocate `amount` across at most `max_split_legs` adapters, one
+    /// `split_increment_bps` slice at a time, giving each slice to the adapter
+    /// whose `amount_out` grows the most. Each leg pays the protocol and
+    /// integrator fees on its allocation. Returns (adapter, amount, amount_out) legs.
+    fn split_legs(
+        &self,
+        from_chain: U256,
+        to_chain: U256,
+        token: Address,
+        amount: U256,
+        integrator_fee_bps: U256,
+    ) -> Result<Vec<(Address, U256, U256)>, SettlementError> {
+        let (max_legs, increment_bps) = self.get_split_config();
+        let max_legs: usize = max_legs.saturating_to();
+        let slices = MAX_BPS / increment_bps.to::<u64>();
+        let slice = amount * increment_bps / U256::from(MAX_BPS);
+        let (token_price, token_unit) = self.token_pricing(from_chain, token)?;
+
+        // (adapter, allocated, amount_out) for every registered adapter
+        let mut legs: Vec<(Address, U256, U256)> = (0..self.bridge_adapters.len())
+            .filter_map(|i| self.bridge_adapters.get(i))
+            .map(|adapter| (adapter, U256::ZERO, U256::ZERO))
+            .collect();
+        let mut allocated = U256::ZERO;
+
+        for step in 0..slices {
+            // The last slice also carries the rounding remainder
+            let chunk = if step + 1 == slices { amount - allocated } else { slice };
+            if chunk == U256::ZERO {
+                continue;
+            }
+            let used = legs.iter().filter(|leg| leg.1 > U256::ZERO).count();
+
+            let mut best: Option<(usize, U256, U256)> = None;
+            for (i, (adapter, allocation, amount_out)) in legs.iter().enumerate() {
+                if *allocation == U256::ZERO && used >= max_legs {
+                    continue;
+                }
+                if self.route_paused(*adapter, to_chain, token) {
+                    continue;
+                }
+                let next = allocation + chunk;
+                let next_usd = next.saturating_mul(token_price) / token_unit;
+                if next_usd > self.exposure_remaining(*adapter) {
+                    continue;
+                }
+                let (protocol_fee, integrator_fee) = self.transfer_fees(next, integrator_fee_bps);
+                let bridged = next - protocol_fee - integrator_fee;
+                let out = match self.adapter_quote(*adapter, from_chain, to_chain, token, bridged) {
+                    Some(route) => bridged - route.fee,
+                    None => continue,
+                };
+                let gain = out.saturating_sub(*amount_out);
+                if best.map_or(true, |(_, best_gain, _)| gain > best_gain) {
+                    best = Some((i, gain, out));
+                }
+            }
+
+            let (i, _, out) = best.ok_or(NoRouteAvailable {
+                toChain: to_chain,
+                token,
+            })?;
+            legs[i].1 += chunk;
+            legs[i].2 = out;
+            allocated += chunk;
+        }
+
+        legs.retain(|leg| leg.1 > U256::ZERO);
+        Ok(legs)
+    }
+
+    /// Protocol and integrator fees on `amount`, rounded down
+    fn transfer_fees(&self, amount: U256, integrator_fee_bps: U256) -> (U256, U256) {
+        let max_bps = U256::from(MAX_BPS);
+        (
+            amount * self.protocol_fee_bps.get() / max_bps,
+            amount * integrator_fee_bps / max_bps,
+        )
+    }
+
+    fn ensure_deadline(&self, deadline: U256) -> Result<(), SettlementError> {
+        let now = U256::from(self.vm().block_timestamp());
+        if now > deadline {
+            return Err(DeadlineExpired {
+                deadline,
+                timestamp: now,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Take payment for a single-token transfer: native transfers must send
+    /// exactly `amount` as value, ERC-20 transfers none. A native drop needs at
+    /// least its value on top; everything above the transfer amount is returned
+    /// and forwarded to the adapter to fund the drop.
+    fn collect_payment(&mut self, transfer: &Transfer) -> Result<U256, SettlementError> {
+        let is_native = transfer.token == NATIVE_TOKEN;
+        let native_amount = if is_native { transfer.amount } else { U256::ZERO };
+
+        if transfer.native_drop == U256::ZERO {
+            self.ensure_msg_value(native_amount)?;
+        } else {
+            let from_chain = U256::from(self.vm().chain_id());
+            let (_, drop_value) =
+                self.native_drop_pricing(from_chain, transfer.to_chain, transfer.native_drop)?;
+            let expected = native_amount + drop_value;
+            let received = self.vm().msg_value();
+            if received < expected {
+                return Err(InvalidNativeValue { expected, received }.into());
+            }
+        }
+
+        if !is_native {
+            self.pull_tokens(transfer.token, transfer.amount)?;
+        }
+        Ok(self.vm().msg_value() - native_amount)
+    }
+
+    fn ensure_msg_value(&self, expected: U256) -> Result<(), SettlementError> {
+        let received = self.vm().msg_value();
+        if received != expected {
+            return Err(InvalidNativeValue { expected, received }.into());
+        }
+        Ok(())
+    }
+
+    /// Token an adapter bridges for `token`: native transfers go to adapters
+    /// that take the native asset as is, otherwise as WETH if it is configured.
+    /// `None` if the adapter cannot carry the transfer.
+    fn bridged_token(&self, adapter: Address, token: Address) -> Option<Address> {
+        if token != NATIVE_TOKEN {
+            return Some(token);
+        }
+        let supports_native =
+            static_call(self.vm(), adapter, IBridgeAdapterExternal::supportsNativeCall {});
+        if matches!(supports_native, Ok(ref native) if native._0) {
+            return Some(NATIVE_TOKEN);
+        }
+        let weth = self.weth.get();
+        (weth != Address::ZERO).then_some(weth)
+    }
+
+    /// Send router-held `token` (or the native asset) to `to`
+    fn send_tokens(
+        &mut self,
+        token: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), SettlementError> {
+        if token == NATIVE_TOKEN {
+            return send_native(self.vm(), to, amount);
+        }
+        call_token(self.vm(), token, IERC20::transferCall { to, amount })
+    }
+
+    /// Router balance of `token` (or the native asset)
+    fn token_balance(&self, token: Address) -> Result<U256, SettlementError> {
+        let router = self.vm().contract_address();
+        if token == NATIVE_TOKEN {
+            return Ok(self.vm().balance(router));
+        }
+        Ok(static_call(self.vm(), token, IERC20::balanceOfCall { account: router })?._0)
+    }
+
+    /// Pull `amount` of `token` from the caller into the router
+    fn pull_tokens(&mut self, token: Address, amount: U256) -> Result<(), SettlementError> {
+        let transfer = IERC20::transferFromCall {
+            from: self.vm().msg_sender(),
+            to: self.vm().contract_address(),
+            amount,
+        };
+        call_token(self.vm(), token, transfer)
+    }
+
+    /// Send an already-pulled transfer through the best route for `speed_weight_bps`
+    fn route_and_dispatch(
+        &mut self,
+        transfer: &Transfer,
+        speed_weight_bps: U256,
+    ) -> Result<B256, SettlementError> {
+        let from_chain = U256::from(self.vm().chain_id());
+        let route = self.find_best_route(
+            from_chain,
+            transfer.to_chain,
+            transfer.token,
+            transfer.amount,
+            speed_weight_bps,
+            transfer.route_options(),
+        )?;
+        if route.amount_out < transfer.min_amount_out {
+            return Err(InsufficientOutput {
+                amountOut: route.amount_out,
+                minAmountOut: transfer.min_amount_out,
+            }
+            .into());
+        }
+
+        self.dispatch(route.adapter, transfer)
+    }
+
+    /// Hand the router-held funds, less the protocol and integrator fees, to the
+    /// adapter with the user's bounds and emit `CrossChainTransferInitiated`. The
+    /// fees, transfer count and USD volume are booked before the adapter is called
+    /// and undone if it fails. Native value is forwarded to native-capable adapters
+    /// and wrapped to WETH for the rest; the native drop value is always sent
+    /// along. Returns the transfer id.
+    fn dispatch(&mut self, adapter: Address, transfer: &Transfer) -> Result<B256, SettlementError> {
+        if self.paused_adapters.get(adapter) {
+            return Err(AdapterPaused { adapter }.into());
+        }
+        // Earlier legs of a split or batch have used up part of the limits
+        self.ensure_within_limits(transfer.token, self.vm().msg_sender(), transfer.amount)?;
+        let bridged_token = self
+            .bridged_token(adapter, transfer.token)
+            .ok_or(NoRouteAvailable {
+                toChain: transfer.to_chain,
+                token: transfer.token,
+            })?;
+        let fees = self.transfer_fees(transfer.amount, transfer.integrator_fee_bps);
+        let amount = transfer.amount - fees.0 - fees.1;
+
+        // Volume counts the user's full amount, fees included
+        let from_chain = U256::from(self.vm().chain_id());
+        let (token_price, token_unit) = self.token_pricing(from_chain, transfer.token)?;
+        let volume_usd = transfer.amount.saturating_mul(token_price) / token_unit;
+        self.ensure_exposure(adapter, volume_usd)?;
+
+        let transfer_id = self.book_transfer(adapter, transfer, amount, fees, volume_usd);
+        if let Err(err) = self.bridge_through(adapter, bridged_token, transfer_id, transfer, amount)
+        {
+            self.unbook_transfer(adapter, transfer_id, transfer, fees, volume_usd);
+            return Err(err);
+        }
+
+        log(self.vm(), CrossChainTransferInitiated {
+            transferId: transfer_id,
+            sender: self.vm().msg_sender(),
+            recipient: transfer.recipient,
+            bridgeAdapter: adapter,
+            token: transfer.token,
+            amount,
+            toChain: transfer.to_chain,
+            integrator: transfer.integrator,
+        });
+
+        Ok(transfer_id)
+    }
+
+    /// Approve or wrap `amount` for the adapter and call `bridgeTokens` for
+    /// `transfer_id`. A failed bridge call clears the approval and unwraps again
+    /// before returning.
+    fn bridge_through(
+        &mut self,
+        adapter: Address,
+        bridged_token: Address,
+        transfer_id: B256,
+        transfer: &Transfer,
+        amount: U256,
+    ) -> Result<(), SettlementError> {
+        let wrapped = transfer.token == NATIVE_TOKEN && bridged_token != NATIVE_TOKEN;
+        let approve = |amount| IERC20::approveCall {
+            spender: adapter,
+            amount,
+        };
+
+        let bridged_value = if bridged_token == NATIVE_TOKEN {
+            amount
+        } else {
+            if wrapped {
+                let deposit = IWETH::depositCall {};
+                call_with_value(self.vm(), bridged_token, amount, deposit)?;
+            }
+            call_token(self.vm(), bridged_token, approve(amount))?;
+            U256::ZERO
+        };
+
+        let bridged = call_with_value(
+            self.vm(),
+            adapter,
+            bridged_value + transfer.native_drop_value,
+            IBridgeAdapterExternal::bridgeTokensCall {
+                transferId: transfer_id,
+                toChain: transfer.to_chain,
+                token: bridged_token,
+                amount,
+                recipient: transfer.recipient,
+                minAmountOut: transfer.min_amount_out,
+                deadline: transfer.deadline,
+                nativeDropAmount: transfer.native_drop,
+                data: transfer.payload.clone().into(),
+            },
+        );
+        if let Err(err) = bridged {
+            // Only matters when the caller recovers from the failure
+            if bridged_token != NATIVE_TOKEN {
+                call_token(self.vm(), bridged_token, approve(U256::ZERO))?;
+            }
+            if wrapped {
+                let withdraw = IWETH::withdrawCall { amount };
+                call_contract(self.vm(), bridged_token, withdraw)?;
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Accrue the fees, count the transfer and its volume against the stats, rate
+    /// limits and `adapter`'s exposure, and record the bridged `amount` for
+    /// refunds. Returns the transfer id.
+    fn book_transfer(
+        &mut self,
+        adapter: Address,
+        transfer: &Transfer,
+        amount: U256,
+        (protocol_fee, integrator_fee): (U256, U256),
+        volume_usd: U256,
+    ) -> B256 {
+        let accrued = self.accrued_fees.get(transfer.token);
+        self.accrued_fees.setter(transfer.token).set(accrued + protocol_fee);
+
+        let nonce = self.total_transfers.get();
+        self.total_transfers.set(nonce + U256::from(1));
+        let total_volume = self.total_volume_usd.get();
+        self.total_volume_usd.set(total_volume.saturating_add(volume_usd));
+        if transfer.integrator != Address::ZERO {
+            self.credit_integrator(transfer, integrator_fee, volume_usd);
+        }
+
+        let sender = self.vm().msg_sender();
+        self.consume_rate_limits(transfer.token, sender, transfer.amount);
+
+        let transfer_id = self.transfer_id(nonce);
+        self.transfer_senders.setter(transfer_id).set(sender);
+        self.transfer_tokens.setter(transfer_id).set(transfer.token);
+        self.transfer_amounts.setter(transfer_id).set(amount);
+
+        let exposure = self.adapter_exposure.get(adapter);
+        self.adapter_exposure.setter(adapter).set(exposure.saturating_add(volume_usd));
+        self.transfer_adapters.setter(transfer_id).set(adapter);
+        self.transfer_volumes_usd.setter(transfer_id).set(volume_usd);
+        self.transfer_statuses.setter(transfer_id).set(U8::from(TRANSFER_PENDING));
+        transfer_id
+    }
+
+    /// Reverse `book_transfer` when the caller recovers from a failed bridge call
+    fn unbook_transfer(
+        &mut self,
+        adapter: Address,
+        transfer_id: B256,
+        transfer: &Transfer,
+        (protocol_fee, integrator_fee): (U256, U256),
+        volume_usd: U256,
+    ) {
+        let accrued = self.accrued_fees.get(transfer.token);
+        self.accrued_fees.setter(transfer.token).set(accrued - protocol_fee);
+
+        let transfers = self.total_transfers.get();
+        self.total_transfers.set(transfers - U256::from(1));
+        let total_volume = self.total_volume_usd.get();
+        self.total_volume_usd.set(total_volume.saturating_sub(volume_usd));
+        if transfer.integrator != Address::ZERO {
+            self.debit_integrator(transfer, integrator_fee, volume_usd);
+        }
+        let sender = self.vm().msg_sender();
+        self.release_rate_limits(transfer.token, sender, transfer.amount);
+
+        self.transfer_senders.setter(transfer_id).set(Address::ZERO);
+        self.transfer_tokens.setter(transfer_id).set(Address::ZERO);
+        self.transfer_amounts.setter(transfer_id).set(U256::ZERO);
+
+        let exposure = self.adapter_exposure.get(adapter);
+        self.adapter_exposure.setter(adapter).set(exposure.saturating_sub(volume_usd));
+        self.transfer_adapters.setter(transfer_id).set(Address::ZERO);
+        self.transfer_volumes_usd.setter(transfer_id).set(U256::ZERO);
+        self.transfer_statuses.setter(transfer_id).set(U8::ZERO);
+    }
+
+    /// Accrue `fee` to the transfer's integrator and add to its stats
+    fn credit_integrator(&mut self, transfer: &Transfer, fee: U256, volume_usd: U256) {
+        let (integrator, token) = (transfer.integrator, transfer.token);
+        let fees = self.integrator_fees.getter(integrator).get(token);
+        self.integrator_fees.setter(integrator).setter(token).set(fees + fee);
+        let liabilities = self.integrator_fee_liabilities.get(token);
+        self.integrator_fee_liabilities.setter(token).set(liabilities + fee);
+
+        let transfers = self.integrator_transfers.get(integrator);
+        self.integrator_transfers.setter(integrator).set(transfers + U256::from(1));
+        let volume = self.integrator_volume_usd.get(integrator);
+        self.integrator_volume_usd.setter(integrator).set(volume.saturating_add(volume_usd));
+    }
+
+    /// Reverse `credit_integrator`
+    fn debit_integrator(&mut self, transfer: &Transfer, fee: U256, volume_usd: U256) {
+        let (integrator, token) = (transfer.integrator, transfer.token);
+        let fees = self.integrator_fees.getter(integrator).get(token);
+        self.integrator_fees.setter(integrator).setter(token).set(fees - fee);
+        let liabilities = self.integrator_fee_liabilities.get(token);
+        self.integrator_fee_liabilities.setter(token).set(liabilities - fee);
+
+        let transfers = self.integrator_transfers.get(integrator);
+        self.integrator_transfers.setter(integrator).set(transfers - U256::from(1));
+        let volume = self.integrator_volume_usd.get(integrator);
+        self.integrator_volume_usd.setter(integrator).set(volume.saturating_sub(volume_usd));
+    }
+
+    /// Unique id for the `nonce`-th transfer: keccak256(abi.encode(chainid, this, nonce))
+    fn transfer_id(&self, nonce: U256) -> B256 {
+        let chain_id = U256::from(self.vm().chain_id());
+        keccak((chain_id, self.vm().contract_address(), nonce).abi_encode())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::access_control::{ADMIN_ROLE, ORACLE_UPDATER_ROLE, RELAYER_ROLE};
+    use crate::events::{OperationQueued, Paused as PausedEvent, StorageMigrated, Upgraded};
+    use crate::interfaces::IERC1822Proxiable;
+    use crate::events::OwnershipTransferStarted;
+    use alloy_sol_types::{SolCall, SolEvent};
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::repeat_byte(0xAA);
+    const ORACLE: Address = Address::repeat_byte(0x01);
+    const TOKEN: Address = Address::repeat_byte(0x30);
+    const CHEAP: Address = Address::repeat_byte(0x10);
+    const FAST: Address = Address::repeat_byte(0x11);
+    const AMOUNT: u64 = 1_000_000_000; // 1,000 tokens with 6 decimals
+
+    fn setup() -> (TestVM, SettlementSwitch) {
+        let vm = TestVM::default();
+        vm.set_sender(OWNER);
+        let mut contract = SettlementSwitch::from(&vm);
+        contract.initialize(ORACLE).unwrap();
+        vm.set_code(TOKEN, vec![0xfe]);
+        (vm, contract)
+    }
+
+    /// Token at $1 with 6 decimals, gas at $0.05 per 100k gas on the source chain
+    fn mock_pricing(vm: &TestVM) {
+        let price = IPriceOracleExternal::getTokenPriceCall { token: TOKEN };
+        let usd = U256::from(100_000_000);
+        vm.mock_static_call(
+            ORACLE,
+            price.abi_encode(),
+            Ok(IPriceOracleExternal::getTokenPriceCall::abi_encode_returns(&(usd,))),
+        );
+        vm.mock_static_call(
+            TOKEN,
+            IERC20Metadata::decimalsCall {}.abi_encode(),
+            Ok(IERC20Metadata::decimalsCall::abi_encode_returns(&(6u8,))),
+        );
+        for gas in [100_000u64, 300_000] {
+            let cost = IPriceOracleExternal::calculateGasCostCall {
+                chainId: U256::from(vm.chain_id()),
+                gasAmount: U256::from(gas),
+            };
+            let usd = U256::from(gas * 50);
+            vm.mock_static_call(
+                ORACLE,
+                cost.abi_encode(),
+                Ok(IPriceOracleExternal::calculateGasCostCall::abi_encode_returns(&(usd,))),
+            );
+        }
+    }
+
+    fn mock_route(vm: &TestVM, adapter: Address, time: u64, gas: u64, fee: u64) {
+        mock_route_for(vm, adapter, AMOUNT, time, gas, fee);
+    }
+
+    fn mock_route_for(vm: &TestVM, adapter: Address, amount: u64, time: u64, gas: u64, fee: u64) {
+        let quote = IBridgeAdapterExternal::getRouteCall {
+            fromChain: U256::from(vm.chain_id()),
+            toChain: U256::from(137),
+            token: TOKEN,
+            amount: U256::from(amount),
+        };
+        let route = (
+            String::from("Bridge"),
+            U256::from(time),
+            U256::from(gas),
+            U256::from(fee),
+            true,
+        );
+        vm.mock_static_call(
+            adapter,
+            quote.abi_encode(),
+            Ok(IBridgeAdapterExternal::getRouteCall::abi_encode_returns(&route)),
+        );
+    }
+
+    /// CHEAP: $0.051 total, 15 minutes. FAST: $0.155 total, 1 minute.
+    fn setup_routes() -> (TestVM, SettlementSwitch) {
+        let (vm, mut contract) = setup();
+        contract.set_token_support(TOKEN, true).unwrap();
+        contract.add_bridge_adapter(CHEAP).unwrap();
+        contract.add_bridge_adapter(FAST).unwrap();
+        mock_pricing(&vm);
+        mock_route(&vm, CHEAP, 900, 100_000, 1_000);
+        mock_route(&vm, FAST, 60, 300_000, 5_000);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_settlement_switch_initialization() {
+        let (vm, mut contract) = setup();
+        assert_eq!(contract.ownable.get_owner(), OWNER);
+        assert_eq!(contract.price_oracle.get(), ORACLE);
+        assert!(contract.initialize(ORACLE).is_err());
+
+        // OwnershipTransferred + RoleGranted + PriceOracleUpdated
+        assert_eq!(vm.get_emitted_logs().len(), 3);
+    }
+
+    #[test]
+    fn test_adapter_registry_emits_events() {
+        let (vm, mut contract) = setup();
+        let adapter = Address::repeat_byte(0x10);
+
+        contract.add_bridge_adapter(adapter).unwrap();
+        assert!(contract.add_bridge_adapter(adapter).is_err());
+        assert_eq!(contract.get_bridge_adapters(), vec![adapter]);
+
+        contract.remove_bridge_adapter(adapter).unwrap();
+        assert!(contract.get_bridge_adapters().is_empty());
+        assert_eq!(contract.get_adapter_count(), U256::ZERO);
+
+        let logs = vm.get_emitted_logs();
+        let topics: Vec<B256> = logs.iter().map(|(topics, _)| topics[0]).collect();
+        assert!(topics.contains(&BridgeAdapterAdded::SIGNATURE_HASH));
+        assert!(topics.contains(&BridgeAdapterRemoved::SIGNATURE_HASH));
+    }
+
+    #[test]
+    fn test_execute_emits_transfer_initiated() {
+        let (vm, mut contract) = setup_routes();
+
+        let recipient = Address::repeat_byte(0x20);
+        contract
+            .execute_best_route(
+                U256::from(137),
+                TOKEN,
+                U256::from(AMOUNT),
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], CrossChainTransferInitiated::SIGNATURE_HASH);
+        assert_eq!(topics[1], contract.transfer_id(U256::ZERO));
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, CHEAP);
+    }
+
+    #[test]
+    fn test_token_allowlist() {
+        let (vm, mut contract) = setup();
+        let other = Address::repeat_byte(0x31);
+        contract.add_bridge_adapter(Address::repeat_byte(0x10)).unwrap();
+
+        contract.set_token_support(TOKEN, true).unwrap();
+        contract.set_token_support(other, true).unwrap();
+        contract.set_token_support(TOKEN, false).unwrap();
+        assert!(!contract.is_token_supported(TOKEN));
+        assert_eq!(contract.get_supported_tokens(), vec![other]);
+
+        let amount = U256::from(1_000);
+        let recipient = Address::repeat_byte(0x20);
+        assert!(matches!(
+            contract.get_routes(
+                U256::from(1),
+                U256::from(137),
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                false,
+                U256::ZERO,
+            ),
+            Err(SettlementError::TokenNotSupported(_))
+        ));
+        assert!(matches!(
+            contract.execute_best_route(
+                U256::from(137),
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::TokenNotSupported(_))
+        ));
+
+        vm.set_sender(Address::repeat_byte(0xBB));
+        assert!(matches!(
+            contract.set_token_support(TOKEN, true),
+            Err(SettlementError::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_best_route_follows_speed_weight() {
+        let (vm, mut contract) = setup_routes();
+        let best = |contract: &SettlementSwitch, weight: u64| {
+            contract.get_best_route(
+                U256::from(vm.chain_id()),
+                U256::from(137),
+                TOKEN,
+                U256::from(AMOUNT),
+                Address::ZERO,
+                U256::from(weight),
+                U256::ZERO,
+                false,
+                U256::ZERO,
+            )
+        };
+
+        let cheapest = best(&contract, 0).unwrap();
+        assert_eq!(cheapest.0, CHEAP);
+        assert_eq!(cheapest.3, U256::from(5_000_000)); // gas: $0.05
+        assert_eq!(cheapest.11, U256::from(5_100_000)); // gas + $0.001 fee
+        assert_eq!(cheapest.12, U256::from(AMOUNT - 1_000));
+        assert_eq!(best(&contract, 10_000).unwrap().0, FAST);
+
+        // At $0.0001/s the 14 minutes saved are not worth $0.104 ...
+        assert_eq!(best(&contract, 5_000).unwrap().0, CHEAP);
+        // ... at $0.001/s they are
+        contract.set_value_of_time(U256::from(100_000)).unwrap();
+        assert_eq!(best(&contract, 5_000).unwrap().0, FAST);
+
+        assert!(matches!(
+            best(&contract, 10_001),
+            Err(SettlementError::InvalidSpeedWeight(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_routes_sorted_and_capped() {
+        let (vm, mut contract) = setup_routes();
+        let routes = |contract: &SettlementSwitch, weight: u64| {
+            contract
+                .get_routes(
+                    U256::from(vm.chain_id()),
+                    U256::from(137),
+                    TOKEN,
+                    U256::from(AMOUNT),
+                    Address::ZERO,
+                    U256::from(weight),
+                    U256::ZERO,
+                    false,
+                    U256::ZERO,
+                )
+                .unwrap()
+        };
+
+        let by_cost = routes(&contract, 0);
+        assert_eq!(by_cost.iter().map(|r| r.0).collect::<Vec<_>>(), vec![CHEAP, FAST]);
+        let fast = &by_cost[1];
+        assert_eq!(fast.2, U256::from(60)); // ETA
+        assert_eq!(fast.3, U256::from(15_000_000)); // gas: $0.15
+        assert_eq!(fast.4, U256::from(5_000)); // fee in tokens
+        assert_eq!(fast.5, U256::from(500_000)); // fee: $0.005
+        assert_eq!(fast.11, U256::from(15_500_000));
+        assert_eq!(fast.12, U256::from(AMOUNT - 5_000));
+
+        let by_time = routes(&contract, 10_000);
+        assert_eq!(by_time.iter().map(|r| r.0).collect::<Vec<_>>(), vec![FAST, CHEAP]);
+
+        assert_eq!(contract.get_max_routes(), U256::from(5));
+        contract.set_max_routes(U256::from(1)).unwrap();
+        assert_eq!(routes(&contract, 10_000).len(), 1);
+        assert!(matches!(
+            contract.set_max_routes(U256::ZERO),
+            Err(SettlementError::InvalidMaxRoutes(_))
+        ));
+
+        vm.set_sender(Address::repeat_byte(0xBB));
+        assert!(matches!(
+            contract.set_max_routes(U256::from(3)),
+            Err(SettlementError::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_execute_with_adapter_enforces_quote() {
+        let (vm, mut contract) = setup_routes();
+        vm.set_block_timestamp(1_000);
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let quoted_out = U256::from(AMOUNT - 5_000);
+        let (deadline, no_drop) = (U256::from(1_000), U256::ZERO);
+
+        // The slower, cheaper route is not what the user picked
+        let transfer_id = contract
+            .execute_with_adapter(
+                FAST, to_chain, TOKEN, amount, recipient, quoted_out, deadline, no_drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(transfer_id, contract.transfer_id(U256::ZERO));
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, FAST);
+
+        assert!(matches!(
+            contract.execute_with_adapter(
+                FAST,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                quoted_out + U256::from(1),
+                deadline,
+                no_drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::InsufficientOutput(_))
+        ));
+
+        vm.set_block_timestamp(1_001);
+        assert!(matches!(
+            contract.execute_with_adapter(
+                FAST, to_chain, TOKEN, amount, recipient, quoted_out, deadline, no_drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::DeadlineExpired(_))
+        ));
+
+        let unknown = Address::repeat_byte(0x12);
+        assert!(matches!(
+            contract.execute_with_adapter(
+                unknown, to_chain, TOKEN, amount, recipient, quoted_out, U256::MAX, no_drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::AdapterNotFound(_))
+        ));
+        assert_eq!(contract.get_total_transfers(), U256::from(1));
+    }
+
+    #[test]
+    fn test_execution_bounds_reach_adapter() {
+        let (vm, mut contract) = setup_routes();
+        vm.set_block_timestamp(1_000);
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let min_out = U256::from(AMOUNT - 1_000);
+        let deadline = U256::from(2_000);
+        let execute = |contract: &mut SettlementSwitch, min_out: U256, deadline: U256| {
+            let (weight, no_drop) = (U256::ZERO, U256::ZERO);
+            contract.execute_best_route(
+                to_chain, TOKEN, amount, recipient, weight, min_out, deadline, no_drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+
+        assert!(matches!(
+            execute(&mut contract, min_out + U256::from(1), deadline),
+            Err(SettlementError::InsufficientOutput(_))
+        ));
+        assert!(matches!(
+            execute(&mut contract, min_out, U256::from(999)),
+            Err(SettlementError::DeadlineExpired(_))
+        ));
+
+        // The adapter receives the user's bounds and its revert bubbles up
+        let bridge = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers()),
+            toChain: to_chain,
+            token: TOKEN,
+            amount,
+            recipient,
+            minAmountOut: min_out,
+            deadline,
+            nativeDropAmount: U256::ZERO,
+            data: Vec::new().into(),
+        };
+        vm.mock_call(CHEAP, bridge.abi_encode(), Err(vec![0xff]));
+        assert!(matches!(
+            execute(&mut contract, min_out, deadline),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+        assert!(execute(&mut contract, min_out - U256::from(1), deadline).unwrap());
+    }
+
+    /// Halves of AMOUNT: CHEAP's fee grows steeply with size, FAST's linearly
+    fn setup_split() -> (TestVM, SettlementSwitch) {
+        let (vm, mut contract) = setup_routes();
+        contract.set_split_config(U256::from(2), U256::from(5_000)).unwrap();
+        mock_route_for(&vm, CHEAP, AMOUNT / 2, 900, 100_000, 100);
+        mock_route_for(&vm, CHEAP, AMOUNT, 900, 100_000, 10_000);
+        mock_route_for(&vm, FAST, AMOUNT / 2, 60, 300_000, 2_000);
+        mock_route_for(&vm, FAST, AMOUNT, 60, 300_000, 4_000);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_split_quote_maximises_amount_out() {
+        let (vm, mut contract) = setup_split();
+        let chain = U256::from(vm.chain_id());
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let half = U256::from(AMOUNT / 2);
+
+        let (adapters, amounts, outs, total) =
+            contract.get_split_quote(chain, to_chain, TOKEN, amount, U256::ZERO).unwrap();
+        assert_eq!(adapters, vec![CHEAP, FAST]);
+        assert_eq!(amounts, vec![half, half]);
+        assert_eq!(outs, vec![half - U256::from(100), half - U256::from(2_000)]);
+        assert_eq!(total, U256::from(AMOUNT - 2_100));
+
+        // One leg only: both halves stay on the adapter that won the first slice
+        contract.set_split_config(U256::from(1), U256::from(5_000)).unwrap();
+        let (adapters, _, _, total) =
+            contract.get_split_quote(chain, to_chain, TOKEN, amount, U256::ZERO).unwrap();
+        assert_eq!(adapters, vec![CHEAP]);
+        assert_eq!(total, U256::from(AMOUNT - 10_000));
+
+        assert!(matches!(
+            contract.set_split_config(U256::from(2), U256::from(3_000)),
+            Err(SettlementError::InvalidSplitConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_execute_split_is_all_or_nothing() {
+        let (vm, mut contract) = setup_split();
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let min_out = U256::from(AMOUNT - 2_100);
+
+        let ids = contract
+            .execute_split(
+                to_chain, TOKEN, amount, recipient, min_out, U256::MAX, Address::ZERO, U256::ZERO,
+            )
+            .unwrap();
+        let expected = vec![contract.transfer_id(U256::ZERO), contract.transfer_id(U256::from(1))];
+        assert_eq!(ids, expected);
+
+        let too_high = min_out + U256::from(1);
+        assert!(matches!(
+            contract.execute_split(
+                to_chain, TOKEN, amount, recipient, too_high, U256::MAX, Address::ZERO, U256::ZERO,
+            ),
+            Err(SettlementError::InsufficientOutput(_))
+        ));
+
+        // A failing second leg fails the whole call
+        let half = U256::from(AMOUNT / 2);
+        let fast_leg = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers() + U256::from(1)),
+            toChain: to_chain,
+            token: TOKEN,
+            amount: half,
+            recipient,
+            minAmountOut: half - U256::from(2_000),
+            deadline: U256::MAX,
+            nativeDropAmount: U256::ZERO,
+            data: Vec::new().into(),
+        };
+        vm.mock_call(FAST, fast_leg.abi_encode(), Err(vec![0xff]));
+        assert!(matches!(
+            contract.execute_split(
+                to_chain, TOKEN, amount, recipient, min_out, U256::MAX, Address::ZERO, U256::ZERO,
+            ),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_execute_batch_pulls_once_and_refunds_skipped_legs() {
+        let (vm, mut contract) = setup_routes();
+        let (alice, bob) = (Address::repeat_byte(0x20), Address::repeat_byte(0x21));
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let legs = vec![
+            (to_chain, TOKEN, amount, alice, U256::ZERO),
+            (to_chain, TOKEN, amount, bob, U256::ZERO),
+        ];
+
+        // The batch total is pulled with a single transferFrom
+        let pull = IERC20::transferFromCall {
+            from: OWNER,
+            to: vm.contract_address(),
+            amount: amount * U256::from(2),
+        };
+        vm.mock_call(TOKEN, pull.abi_encode(), Ok(false.abi_encode()));
+        assert!(matches!(
+            contract.execute_batch(
+                legs.clone(), U256::ZERO, U256::MAX, false, Address::ZERO, U256::ZERO,
+            ),
+            Err(SettlementError::TokenTransferFailed(_))
+        ));
+        vm.mock_call(TOKEN, pull.abi_encode(), Ok(true.abi_encode()));
+
+        // Bob's leg, the second transfer from now, fails at the adapter
+        let fail_bob_leg = |contract: &SettlementSwitch| {
+            let bob_leg = IBridgeAdapterExternal::bridgeTokensCall {
+                transferId: contract.transfer_id(contract.get_total_transfers() + U256::from(1)),
+                toChain: to_chain,
+                token: TOKEN,
+                amount,
+                recipient: bob,
+                minAmountOut: U256::ZERO,
+                deadline: U256::MAX,
+                nativeDropAmount: U256::ZERO,
+                data: Vec::new().into(),
+            };
+            vm.mock_call(CHEAP, bob_leg.abi_encode(), Err(vec![0xff]));
+        };
+        fail_bob_leg(&contract);
+        assert!(matches!(
+            contract.execute_batch(
+                legs.clone(), U256::ZERO, U256::MAX, false, Address::ZERO, U256::ZERO,
+            ),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+
+        // The test VM does not roll back alice's booking, so bob's id moved on
+        fail_bob_leg(&contract);
+        let before = contract.get_total_transfers();
+        let ids = contract
+            .execute_batch(legs, U256::ZERO, U256::MAX, true, Address::ZERO, U256::ZERO)
+            .unwrap();
+        assert_eq!(ids, vec![contract.transfer_id(before), B256::ZERO]);
+        assert_eq!(contract.get_total_transfers(), before + U256::from(1));
+
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let refund = BatchLegRefunded::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(refund.index, U256::from(1));
+        assert_eq!(refund.recipient, bob);
+        assert_eq!(refund.amount, amount);
+    }
+
+    #[test]
+    fn test_best_route_skips_unquoted_adapters() {
+        let (vm, mut contract) = setup_routes();
+        // No getRoute mock: the call returns no data and the adapter is skipped
+        contract.remove_bridge_adapter(CHEAP).unwrap();
+        contract.remove_bridge_adapter(FAST).unwrap();
+        contract.add_bridge_adapter(Address::repeat_byte(0x12)).unwrap();
+
+        assert!(matches!(
+            contract.get_best_route(
+                U256::from(vm.chain_id()),
+                U256::from(137),
+                TOKEN,
+                U256::from(AMOUNT),
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+                false,
+                U256::ZERO,
+            ),
+            Err(SettlementError::NoRouteAvailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_native_transfers_forward_value_or_wrap() {
+        let (vm, mut contract) = setup();
+        let weth = Address::repeat_byte(0x40);
+        vm.set_code(weth, vec![0xfe]);
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(10).pow(U256::from(18)));
+        contract.set_token_support(NATIVE_TOKEN, true).unwrap();
+        contract.add_bridge_adapter(CHEAP).unwrap();
+        contract.add_bridge_adapter(FAST).unwrap();
+        mock_pricing(&vm);
+
+        // ETH at $2,000 on the source chain
+        let native_price = IPriceOracleExternal::getNativeTokenPriceCall {
+            chainId: U256::from(vm.chain_id()),
+        };
+        vm.mock_static_call(
+            ORACLE,
+            native_price.abi_encode(),
+            Ok(IPriceOracleExternal::getNativeTokenPriceCall::abi_encode_returns(&(
+                U256::from(200_000_000_000u64),
+            ))),
+        );
+        // FAST bridges ETH itself; CHEAP only takes WETH
+        vm.mock_static_call(
+            FAST,
+            IBridgeAdapterExternal::supportsNativeCall {}.abi_encode(),
+            Ok(IBridgeAdapterExternal::supportsNativeCall::abi_encode_returns(&(true,))),
+        );
+        let quotes = [(FAST, NATIVE_TOKEN, 60, 300_000), (CHEAP, weth, 900, 100_000)];
+        for (adapter, token, time, gas) in quotes {
+            let quote = IBridgeAdapterExternal::getRouteCall {
+                fromChain: U256::from(vm.chain_id()),
+                toChain: to_chain,
+                token,
+                amount,
+            };
+            let route =
+                (String::from("Bridge"), U256::from(time), U256::from(gas), U256::ZERO, true);
+            vm.mock_static_call(
+                adapter,
+                quote.abi_encode(),
+                Ok(IBridgeAdapterExternal::getRouteCall::abi_encode_returns(&route)),
+            );
+        }
+        let execute = |contract: &mut SettlementSwitch, token| {
+            contract.execute_best_route(
+                to_chain,
+                token,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+
+        // Without WETH only the native adapter can carry ETH
+        vm.set_value(amount);
+        execute(&mut contract, NATIVE_TOKEN).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, FAST);
+        assert_eq!(event.token, NATIVE_TOKEN);
+
+        // The cheaper ERC-20-only adapter becomes usable once WETH is set
+        contract.set_weth(weth).unwrap();
+        assert_eq!(contract.get_weth(), weth);
+        execute(&mut contract, NATIVE_TOKEN).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, CHEAP);
+
+        // A wrapped leg that fails at the adapter is unwrapped and refunded as ETH
+        let bob = Address::repeat_byte(0x21);
+        let wrapped_leg = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers()),
+            toChain: to_chain,
+            token: weth,
+            amount,
+            recipient: bob,
+            minAmountOut: U256::ZERO,
+            deadline: U256::MAX,
+            nativeDropAmount: U256::ZERO,
+            data: Vec::new().into(),
+        };
+        vm.mock_call(CHEAP, wrapped_leg.abi_encode(), Err(vec![0xff]));
+        let legs = vec![(to_chain, NATIVE_TOKEN, amount, bob, U256::ZERO)];
+        let ids = contract
+            .execute_batch(legs, U256::ZERO, U256::MAX, true, Address::ZERO, U256::ZERO)
+            .unwrap();
+        assert_eq!(ids, vec![B256::ZERO]);
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let refund = BatchLegRefunded::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!((refund.token, refund.amount), (NATIVE_TOKEN, amount));
+
+        // The unwrapped ETH comes back through `receive`, which strangers cannot use
+        vm.set_sender(weth);
+        contract.receive().unwrap();
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert!(contract.receive().is_err());
+        vm.set_sender(OWNER);
+
+        vm.mock_call(weth, IWETH::depositCall {}.abi_encode(), Err(vec![]));
+        assert!(matches!(
+            execute(&mut contract, NATIVE_TOKEN),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+
+        vm.set_value(amount - U256::from(1));
+        assert!(matches!(
+            execute(&mut contract, NATIVE_TOKEN),
+            Err(SettlementError::InvalidNativeValue(_))
+        ));
+        // ERC-20 transfers must not carry value
+        contract.set_token_support(TOKEN, true).unwrap();
+        assert!(matches!(
+            execute(&mut contract, TOKEN),
+            Err(SettlementError::InvalidNativeValue(_))
+        ));
+        assert_eq!(contract.get_total_transfers(), U256::from(2));
+    }
+
+    #[test]
+    fn test_native_drop_priced_and_limited_to_capable_adapters() {
+        let (vm, mut contract) = setup_routes();
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let drop = U256::from(500_000_000_000_000_000u64); // 0.5 native on the destination
+
+        // $1 on the destination, $2,000 on the source chain
+        for (chain_id, price) in [(137u64, 100_000_000u64), (vm.chain_id(), 200_000_000_000)] {
+            let call = IPriceOracleExternal::getNativeTokenPriceCall {
+                chainId: U256::from(chain_id),
+            };
+            vm.mock_static_call(
+                ORACLE,
+                call.abi_encode(),
+                Ok(IPriceOracleExternal::getNativeTokenPriceCall::abi_encode_returns(&(
+                    U256::from(price),
+                ))),
+            );
+        }
+        vm.mock_static_call(
+            FAST,
+            IBridgeAdapterExternal::supportsNativeDropCall {}.abi_encode(),
+            Ok(IBridgeAdapterExternal::supportsNativeDropCall::abi_encode_returns(&(true,))),
+        );
+
+        // CHEAP stays cheaper but cannot drop gas, so it is listed last as unavailable
+        let from_chain = U256::from(vm.chain_id());
+        let routes = contract
+            .get_routes(
+                from_chain, to_chain, TOKEN, amount, recipient, U256::ZERO, drop, false, U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(routes.iter().map(|r| (r.0, r.13)).collect::<Vec<_>>(), vec![
+            (FAST, true),
+            (CHEAP, false)
+        ]);
+        assert_eq!(routes[0].10, U256::from(50_000_000)); // drop: $0.50
+        assert_eq!(routes[0].11, U256::from(65_500_000));
+
+        // 0.5 * $1 / $2,000 = 0.00025 source native
+        let drop_value = contract.get_native_drop_value(to_chain, drop).unwrap();
+        assert_eq!(drop_value, U256::from(250_000_000_000_000u64));
+
+        let execute = |contract: &mut SettlementSwitch| {
+            contract.execute_best_route(
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+        vm.set_value(drop_value - U256::from(1));
+        assert!(matches!(execute(&mut contract), Err(SettlementError::InvalidNativeValue(_))));
+
+        vm.set_value(drop_value);
+        execute(&mut contract).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, FAST);
+
+        assert!(matches!(
+            contract.execute_with_adapter(
+                CHEAP, to_chain, TOKEN, amount, recipient, U256::ZERO, U256::MAX, drop,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::NativeDropNotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_payload_reaches_adapter_and_limits_routes() {
+        let (vm, mut contract) = setup_routes();
+        let vault = Address::repeat_byte(0x21);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let payload = vec![0xde, 0xad, 0xbe, 0xef];
+        vm.mock_static_call(
+            FAST,
+            IBridgeAdapterExternal::supportsPayloadCall {}.abi_encode(),
+            Ok(IBridgeAdapterExternal::supportsPayloadCall::abi_encode_returns(&(true,))),
+        );
+
+        // CHEAP stays cheaper but drops payloads, so it is listed last as unavailable
+        let from_chain = U256::from(vm.chain_id());
+        let routes = contract
+            .get_routes(
+                from_chain, to_chain, TOKEN, amount, vault, U256::ZERO, U256::ZERO, true,
+                U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(routes.iter().map(|r| (r.0, r.13)).collect::<Vec<_>>(), vec![
+            (FAST, true),
+            (CHEAP, false)
+        ]);
+
+        // The payload is handed to the adapter unchanged
+        let bridge = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers()),
+            toChain: to_chain,
+            token: TOKEN,
+            amount,
+            recipient: vault,
+            minAmountOut: U256::ZERO,
+            deadline: U256::MAX,
+            nativeDropAmount: U256::ZERO,
+            data: payload.clone().into(),
+        };
+        vm.mock_call(FAST, bridge.abi_encode(), Err(vec![0xff]));
+        let execute = |contract: &mut SettlementSwitch| {
+            contract.execute_best_route(
+                to_chain,
+                TOKEN,
+                amount,
+                vault,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(payload.clone()),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+        assert!(matches!(execute(&mut contract), Err(SettlementError::ExternalCallFailed(_))));
+        vm.mock_call(FAST, bridge.abi_encode(), Ok(vec![]));
+        execute(&mut contract).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.bridgeAdapter, FAST);
+
+        assert!(matches!(
+            contract.execute_with_adapter(
+                CHEAP,
+                to_chain,
+                TOKEN,
+                amount,
+                vault,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(payload.clone()),
+                Address::ZERO,
+                U256::ZERO,
+            ),
+            Err(SettlementError::PayloadNotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_adapter_message_settles_at_receiver() {
+        let (vm, mut contract) = setup_routes();
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+
+        // The adapter is told which transfer it carries
+        let bridge = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers()),
+            toChain: to_chain,
+            token: TOKEN,
+            amount,
+            recipient,
+            minAmountOut: U256::ZERO,
+            deadline: U256::MAX,
+            nativeDropAmount: U256::ZERO,
+            data: Default::default(),
+        };
+        vm.mock_call(CHEAP, bridge.abi_encode(), Err(vec![0xff]));
+        let execute = |contract: &mut SettlementSwitch| {
+            contract.execute_with_adapter(
+                CHEAP,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+        assert!(matches!(execute(&mut contract), Err(SettlementError::ExternalCallFailed(_))));
+        vm.mock_call(CHEAP, bridge.abi_encode(), Ok(vec![]));
+        execute(&mut contract).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.transferId, bridge.transferId);
+
+        // The message the adapter builds from those arguments settles that transfer
+        let message =
+            settlement::encode_message(bridge.transferId, recipient, amount, &bridge.data);
+        let destination = TestVM::default();
+        let (stargate, across, cctp) = (
+            Address::repeat_byte(0x51),
+            Address::repeat_byte(0x52),
+            Address::repeat_byte(0x53),
+        );
+        destination.set_sender(OWNER);
+        let mut receiver = receiver::SettlementReceiver::from(&destination);
+        receiver.initialize(stargate, across, cctp, Address::ZERO, Address::ZERO).unwrap();
+        destination.set_code(TOKEN, vec![0xfe]);
+        destination.set_sender(across);
+        receiver
+            .handle_v3_across_message(TOKEN, amount, Address::repeat_byte(0x60), Bytes(message))
+            .unwrap();
+        assert_eq!(receiver.is_settled(event.transferId), (true, false));
+        assert_eq!(receiver.get_settlement(event.transferId).0, recipient);
+    }
+
+    #[test]
+    fn test_refunds_claimable_by_original_sender() {
+        let (vm, mut contract) = setup_routes();
+        let alice = Address::repeat_byte(0xA1);
+        let amount = U256::from(AMOUNT);
+        vm.set_sender(alice);
+        contract
+            .execute_best_route(
+                U256::from(137),
+                TOKEN,
+                amount,
+                Address::repeat_byte(0x20),
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        let transfer_id = contract.transfer_id(U256::ZERO);
+        assert_eq!(contract.get_transfer(transfer_id), (alice, TOKEN, amount));
+
+        // The bridge pushed the deposit back; the router holds it once
+        let balance_of = IERC20::balanceOfCall {
+            account: vm.contract_address(),
+        };
+        vm.mock_static_call(
+            TOKEN,
+            balance_of.abi_encode(),
+            Ok(IERC20::balanceOfCall::abi_encode_returns(&(amount,))),
+        );
+        assert!(matches!(
+            contract.process_refund(transfer_id, TOKEN, amount),
+            Err(SettlementError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.process_refund(B256::repeat_byte(0x01), TOKEN, amount),
+            Err(SettlementError::TransferNotFound(_))
+        ));
+        contract.process_refund(transfer_id, TOKEN, amount).unwrap();
+        assert_eq!(contract.get_refund(transfer_id), (TOKEN, amount));
+        assert!(matches!(
+            contract.process_refund(transfer_id, TOKEN, U256::from(1)),
+            Err(SettlementError::InsufficientRefundBalance(_))
+        ));
+
+        assert!(matches!(
+            contract.claim_refund(transfer_id),
+            Err(SettlementError::NotTransferSender(_))
+        ));
+        vm.set_sender(alice);
+        assert_eq!(contract.claim_refund(transfer_id).unwrap(), amount);
+        assert_eq!(contract.get_refund(transfer_id), (TOKEN, U256::ZERO));
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], RefundClaimed::SIGNATURE_HASH);
+        assert!(matches!(
+            contract.claim_refund(transfer_id),
+            Err(SettlementError::NoRefundAvailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_native_refund_returns_through_adapter() {
+        let (vm, mut contract) = setup_routes();
+        let alice = Address::repeat_byte(0xA1);
+        let (to_chain, amount) = (U256::from(137), U256::from(10).pow(U256::from(18)));
+        contract.set_token_support(NATIVE_TOKEN, true).unwrap();
+
+        // FAST bridges ETH at $2,000
+        let native_price = IPriceOracleExternal::getNativeTokenPriceCall {
+            chainId: U256::from(vm.chain_id()),
+        };
+        vm.mock_static_call(
+            ORACLE,
+            native_price.abi_encode(),
+            Ok(IPriceOracleExternal::getNativeTokenPriceCall::abi_encode_returns(&(
+                U256::from(200_000_000_000u64),
+            ))),
+        );
+        vm.mock_static_call(
+            FAST,
+            IBridgeAdapterExternal::supportsNativeCall {}.abi_encode(),
+            Ok(IBridgeAdapterExternal::supportsNativeCall::abi_encode_returns(&(true,))),
+        );
+        let quote = IBridgeAdapterExternal::getRouteCall {
+            fromChain: U256::from(vm.chain_id()),
+            toChain: to_chain,
+            token: NATIVE_TOKEN,
+            amount,
+        };
+        let route = (String::from("Bridge"), U256::from(60), U256::from(300_000), U256::ZERO, true);
+        vm.mock_static_call(
+            FAST,
+            quote.abi_encode(),
+            Ok(IBridgeAdapterExternal::getRouteCall::abi_encode_returns(&route)),
+        );
+
+        vm.set_sender(alice);
+        vm.set_value(amount);
+        let transfer_id = contract
+            .execute_with_adapter(
+                FAST,
+                to_chain,
+                NATIVE_TOKEN,
+                amount,
+                Address::repeat_byte(0x20),
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        vm.set_value(U256::ZERO);
+
+        // The bridge returns the deposit to the adapter, which pushes it to the router
+        vm.set_sender(Address::repeat_byte(0x66));
+        assert!(contract.receive().is_err());
+        vm.set_sender(FAST);
+        contract.receive().unwrap();
+        vm.set_balance(vm.contract_address(), amount);
+
+        vm.set_sender(OWNER);
+        contract.process_refund(transfer_id, NATIVE_TOKEN, amount).unwrap();
+        vm.set_sender(alice);
+        assert_eq!(contract.claim_refund(transfer_id).unwrap(), amount);
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let claimed = RefundClaimed::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!((claimed.sender, claimed.token, claimed.amount), (alice, NATIVE_TOKEN, amount));
+
+        // Removed adapters can no longer push funds in
+        vm.set_sender(OWNER);
+        contract.remove_bridge_adapter(FAST).unwrap();
+        vm.set_sender(FAST);
+        assert!(contract.receive().is_err());
+    }
+
+    #[test]
+    fn test_protocol_fee_quoted_accrued_and_withdrawn() {
+        let (vm, mut contract) = setup_routes();
+        let treasury = Address::repeat_byte(0xFE);
+        let max_fee = U256::from(MAX_PROTOCOL_FEE_BPS);
+        assert!(matches!(
+            contract.set_protocol_fee(max_fee + U256::from(1)),
+            Err(SettlementError::FeeTooHigh(_))
+        ));
+        contract.set_protocol_fee(U256::from(10)).unwrap(); // 0.1%
+        contract.set_fee_recipient(treasury).unwrap();
+
+        // Adapters quote what is left after the fee
+        let (fee, bridged) = (1_000_000, AMOUNT - 1_000_000);
+        mock_route_for(&vm, CHEAP, bridged, 900, 100_000, 1_000);
+        mock_route_for(&vm, FAST, bridged, 60, 300_000, 5_000);
+        let (chain, to_chain) = (U256::from(vm.chain_id()), U256::from(137));
+        let amount = U256::from(AMOUNT);
+        let recipient = Address::repeat_byte(0x20);
+        let route = contract
+            .get_best_route(
+                chain, to_chain, TOKEN, amount, recipient, U256::ZERO, U256::ZERO, false,
+                U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(route.6, U256::from(fee));
+        assert_eq!(route.7, U256::from(100_000_000)); // $1.00
+        assert_eq!(route.11, U256::from(105_100_000));
+        assert_eq!(route.12, U256::from(bridged - 1_000));
+
+        let bridge = IBridgeAdapterExternal::bridgeTokensCall {
+            transferId: contract.transfer_id(contract.get_total_transfers()),
+            toChain: to_chain,
+            token: TOKEN,
+            amount: U256::from(bridged),
+            recipient,
+            minAmountOut: route.12,
+            deadline: U256::MAX,
+            nativeDropAmount: U256::ZERO,
+            data: Vec::new().into(),
+        };
+        vm.mock_call(CHEAP, bridge.abi_encode(), Ok(Vec::new()));
+        contract
+            .execute_best_route(
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                route.12,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(contract.get_accrued_fees(TOKEN), U256::from(fee));
+        let transfer_id = contract.transfer_id(U256::ZERO);
+        assert_eq!(contract.get_transfer(transfer_id).2, U256::from(bridged));
+
+        let payout = IERC20::transferCall {
+            to: treasury,
+            amount: U256::from(fee),
+        };
+        vm.mock_call(TOKEN, payout.abi_encode(), Ok(Vec::new()));
+        assert_eq!(contract.withdraw_fees(TOKEN).unwrap(), U256::from(fee));
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], FeesWithdrawn::SIGNATURE_HASH);
+        assert_eq!(contract.get_accrued_fees(TOKEN), U256::ZERO);
+        assert!(matches!(
+            contract.withdraw_fees(TOKEN),
+            Err(SettlementError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn test_integrator_fee_accrues_and_is_claimable() {
+        let (vm, mut contract) = setup_routes();
+        let wallet = Address::repeat_byte(0xB0);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let recipient = Address::repeat_byte(0x20);
+        let execute = |contract: &mut SettlementSwitch, integrator: Address, fee_bps: u64| {
+            contract.execute_with_adapter(
+                CHEAP,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                integrator,
+                U256::from(fee_bps),
+            )
+        };
+
+        // Integrators can take nothing until the owner allows it
+        assert!(matches!(
+            execute(&mut contract, wallet, 20),
+            Err(SettlementError::FeeTooHigh(_))
+        ));
+        assert!(matches!(
+            contract.set_max_integrator_fee(U256::from(MAX_INTEGRATOR_FEE_BPS + 1)),
+            Err(SettlementError::FeeTooHigh(_))
+        ));
+        contract.set_max_integrator_fee(U256::from(50)).unwrap();
+        assert!(matches!(
+            execute(&mut contract, Address::ZERO, 20),
+            Err(SettlementError::InvalidAddress(_))
+        ));
+
+        // 0.2% of 1,000 tokens: the adapter is quoted on the rest
+        let (fee, bridged) = (2_000_000, AMOUNT - 2_000_000);
+        mock_route_for(&vm, CHEAP, bridged, 900, 100_000, 1_000);
+        mock_route_for(&vm, FAST, bridged, 60, 300_000, 5_000);
+        let chain = U256::from(vm.chain_id());
+        let routes = contract
+            .get_routes(
+                chain,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                false,
+                U256::from(20),
+            )
+            .unwrap();
+        assert_eq!(routes[0].8, U256::from(fee));
+        assert_eq!(routes[0].9, U256::from(200_000_000)); // $2.00
+        assert_eq!(routes[0].12, U256::from(bridged - 1_000));
+
+        execute(&mut contract, wallet, 20).unwrap();
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = CrossChainTransferInitiated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!((event.amount, event.integrator), (U256::from(bridged), wallet));
+        assert_eq!(contract.get_integrator_fees(wallet, TOKEN), U256::from(fee));
+        let volume_usd = U256::from(100_000_000_000u64); // $1,000
+        assert_eq!(contract.get_integrator_stats(wallet), (U256::from(1), volume_usd));
+        assert_eq!(contract.get_total_volume_usd(), volume_usd);
+
+        let payout = IERC20::transferCall {
+            to: wallet,
+            amount: U256::from(fee),
+        };
+        vm.mock_call(TOKEN, payout.abi_encode(), Ok(Vec::new()));
+        assert!(matches!(
+            contract.claim_integrator_fees(TOKEN),
+            Err(SettlementError::InvalidAmount(_))
+        ));
+        vm.set_sender(wallet);
+        assert_eq!(contract.claim_integrator_fees(TOKEN).unwrap(), U256::from(fee));
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], IntegratorFeesClaimed::SIGNATURE_HASH);
+        assert_eq!(contract.get_integrator_fees(wallet, TOKEN), U256::ZERO);
+
+        // An empty return is only a success from a deployed token
+        vm.set_sender(OWNER);
+        execute(&mut contract, wallet, 20).unwrap();
+        vm.set_code(TOKEN, Vec::new());
+        vm.set_sender(wallet);
+        assert!(matches!(
+            contract.claim_integrator_fees(TOKEN),
+            Err(SettlementError::TokenTransferFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let (vm, mut contract) = setup();
+        let (new_owner, stranger) = (Address::repeat_byte(0xCC), Address::repeat_byte(0xBB));
+
+        vm.set_sender(stranger);
+        assert!(matches!(
+            contract.ownable.transfer_ownership(stranger),
+            Err(SettlementError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        contract.ownable.transfer_ownership(new_owner).unwrap();
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], OwnershipTransferStarted::SIGNATURE_HASH);
+        assert_eq!(contract.ownable.get_pending_owner(), new_owner);
+
+        // The old owner keeps control until the new one accepts
+        contract.set_value_of_time(U256::from(1)).unwrap();
+        vm.set_sender(stranger);
+        assert!(matches!(
+            contract.accept_ownership(),
+            Err(SettlementError::NotPendingOwner(_))
+        ));
+        vm.set_sender(new_owner);
+        contract.accept_ownership().unwrap();
+        assert_eq!(contract.ownable.get_owner(), new_owner);
+        assert_eq!(contract.ownable.get_pending_owner(), Address::ZERO);
+        vm.set_sender(OWNER);
+        assert!(contract.set_value_of_time(U256::from(2)).is_err());
+
+        // Renouncing locks owner-only methods and does not reopen initialize
+        vm.set_sender(new_owner);
+        contract.renounce_ownership().unwrap();
+        assert_eq!(contract.ownable.get_owner(), Address::ZERO);
+        assert!(contract.set_value_of_time(U256::from(2)).is_err());
+        assert!(matches!(
+            contract.initialize(ORACLE),
+            Err(SettlementError::AlreadyInitialized(_))
+        ));
+    }
+
+    #[test]
+    fn test_roles_gate_adapter_registry_and_fees() {
+        let (vm, mut contract) = setup();
+        let (ops, stranger) = (Address::repeat_byte(0xCC), Address::repeat_byte(0xBB));
+        let adapter = Address::repeat_byte(0x0D);
+        assert_eq!(contract.access_control.get_role_members(ADMIN_ROLE), vec![OWNER]);
+
+        // Only admins grant, and role holders act without the owner key
+        vm.set_sender(stranger);
+        assert!(matches!(
+            contract.access_control.grant_role(ADAPTER_MANAGER_ROLE, stranger),
+            Err(SettlementError::MissingRole(_))
+        ));
+        assert!(matches!(
+            contract.add_bridge_adapter(adapter),
+            Err(SettlementError::MissingRole(_))
+        ));
+        vm.set_sender(OWNER);
+        contract.access_control.grant_role(ADAPTER_MANAGER_ROLE, ops).unwrap();
+        contract.access_control.grant_role(FEE_MANAGER_ROLE, ops).unwrap();
+        contract.access_control.grant_role(ORACLE_UPDATER_ROLE, stranger).unwrap();
+        assert!(contract.access_control.has_role(FEE_MANAGER_ROLE, ops));
+        vm.set_sender(ops);
+        contract.add_bridge_adapter(adapter).unwrap();
+        contract.set_protocol_fee(U256::from(10)).unwrap();
+        assert!(matches!(
+            contract.set_fee_recipient(ops),
+            Err(SettlementError::NotOwner(_))
+        ));
+
+        // Enumeration stays consistent across revoke and renounce
+        vm.set_sender(OWNER);
+        contract.access_control.grant_role(FEE_MANAGER_ROLE, stranger).unwrap();
+        assert_eq!(
+            contract.access_control.get_role_members(FEE_MANAGER_ROLE),
+            vec![ops, stranger]
+        );
+        contract.access_control.revoke_role(FEE_MANAGER_ROLE, ops).unwrap();
+        assert_eq!(contract.access_control.get_role_members(FEE_MANAGER_ROLE), vec![stranger]);
+        vm.set_sender(ops);
+        assert!(contract.set_protocol_fee(U256::from(20)).is_err());
+        contract.access_control.renounce_role(ADAPTER_MANAGER_ROLE).unwrap();
+        assert_eq!(
+            contract.access_control.get_role_member_count(ADAPTER_MANAGER_ROLE),
+            U256::ZERO
+        );
+        assert!(contract.remove_bridge_adapter(adapter).is_err());
+    }
+
+    #[test]
+    fn test_admin_role_follows_ownership() {
+        let (vm, mut contract) = setup();
+        let (new_owner, ops) = (Address::repeat_byte(0xCC), Address::repeat_byte(0xDD));
+        contract.access_control.grant_role(ADMIN_ROLE, ops).unwrap();
+        contract.transfer_ownership(new_owner).unwrap();
+        vm.set_sender(new_owner);
+        contract.accept_ownership().unwrap();
+        assert_eq!(contract.access_control.get_role_members(ADMIN_ROLE), vec![ops, new_owner]);
+
+        // The new owner can revoke admins the old owner left behind
+        contract.access_control.revoke_role(ADMIN_ROLE, ops).unwrap();
+        assert_eq!(contract.access_control.get_role_members(ADMIN_ROLE), vec![new_owner]);
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            contract.access_control.grant_role(ADMIN_ROLE, OWNER),
+            Err(SettlementError::MissingRole(_))
+        ));
+
+        vm.set_sender(new_owner);
+        contract.renounce_ownership().unwrap();
+        assert_eq!(contract.access_control.get_role_member_count(ADMIN_ROLE), U256::ZERO);
+    }
+
+    #[test]
+    fn test_pauses_block_routes_and_only_owner_unpauses() {
+        let (vm, mut contract) = setup_routes();
+        let pauser = Address::repeat_byte(0xCC);
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let routes = |contract: &SettlementSwitch| {
+            contract
+                .get_routes(
+                    U256::from(vm.chain_id()),
+                    to_chain,
+                    TOKEN,
+                    amount,
+                    recipient,
+                    U256::ZERO,
+                    U256::ZERO,
+                    false,
+                    U256::ZERO,
+                )
+                .unwrap()
+        };
+        let execute = |contract: &mut SettlementSwitch, adapter: Address| {
+            contract.execute_with_adapter(
+                adapter,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+
+        vm.set_sender(pauser);
+        assert!(matches!(contract.pause(), Err(SettlementError::MissingRole(_))));
+        vm.set_sender(OWNER);
+        contract.access_control.grant_role(PAUSER_ROLE, pauser).unwrap();
+
+        // A paused adapter is listed unavailable and never picked
+        vm.set_sender(pauser);
+        contract.set_adapter_paused(CHEAP, true).unwrap();
+        let listed = routes(&contract);
+        assert_eq!((listed[0].0, listed[0].13), (FAST, true));
+        assert_eq!((listed[1].0, listed[1].13), (CHEAP, false));
+        assert!(matches!(
+            execute(&mut contract, CHEAP),
+            Err(SettlementError::AdapterPaused(_))
+        ));
+        assert!(matches!(
+            contract.set_adapter_paused(CHEAP, false),
+            Err(SettlementError::NotOwner(_))
+        ));
+
+        // Chain, token and global pauses take every route down
+        contract.set_chain_paused(to_chain, true).unwrap();
+        assert!(routes(&contract).iter().all(|route| !route.13));
+        assert!(matches!(
+            execute(&mut contract, FAST),
+            Err(SettlementError::ChainPaused(_))
+        ));
+        contract.set_token_paused(TOKEN, true).unwrap();
+        contract.pause().unwrap();
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], PausedEvent::SIGNATURE_HASH);
+        assert!(matches!(
+            execute(&mut contract, FAST),
+            Err(SettlementError::RoutingPaused(_))
+        ));
+        assert!(matches!(contract.unpause(), Err(SettlementError::NotOwner(_))));
+
+        vm.set_sender(OWNER);
+        contract.unpause().unwrap();
+        contract.set_chain_paused(to_chain, false).unwrap();
+        assert!(matches!(
+            execute(&mut contract, FAST),
+            Err(SettlementError::TokenPaused(_))
+        ));
+        contract.set_token_paused(TOKEN, false).unwrap();
+        contract.set_adapter_paused(CHEAP, false).unwrap();
+        assert!(routes(&contract).iter().all(|route| route.13));
+        execute(&mut contract, CHEAP).unwrap();
+    }
+
+    #[test]
+    fn test_timelock_queues_sensitive_changes() {
+        let (vm, mut contract) = setup();
+        let (manager, stranger) = (Address::repeat_byte(0xCC), Address::repeat_byte(0xBB));
+        let adapter = Address::repeat_byte(0x0D);
+        let adapter_value = U256::from_be_slice(adapter.as_slice());
+        let delay = 2 * 24 * 60 * 60;
+        vm.set_block_timestamp(1_000);
+        contract.set_timelock_delay(U256::from(delay)).unwrap();
+        contract.access_control.grant_role(ADAPTER_MANAGER_ROLE, manager).unwrap();
+
+        // Direct changes are refused once the delay is set; pausing stays instant
+        assert!(matches!(
+            contract.add_bridge_adapter(adapter),
+            Err(SettlementError::TimelockRequired(_))
+        ));
+        assert!(matches!(
+            contract.set_price_oracle(Address::repeat_byte(0x02)),
+            Err(SettlementError::TimelockRequired(_))
+        ));
+        assert!(matches!(
+            contract.transfer_ownership(stranger),
+            Err(SettlementError::TimelockRequired(_))
+        ));
+        assert!(matches!(
+            contract.set_weth(Address::repeat_byte(0x40)),
+            Err(SettlementError::TimelockRequired(_))
+        ));
+        contract.pause().unwrap();
+
+        vm.set_sender(stranger);
+        assert!(matches!(
+            contract.queue_operation(OP_ADD_BRIDGE_ADAPTER, adapter_value),
+            Err(SettlementError::MissingRole(_))
+        ));
+        vm.set_sender(manager);
+        assert!(matches!(
+            contract.queue_operation(OP_SET_PRICE_ORACLE, adapter_value),
+            Err(SettlementError::NotOwner(_))
+        ));
+        let operation_id = contract.queue_operation(OP_ADD_BRIDGE_ADAPTER, adapter_value).unwrap();
+        let (topics, _) = vm.get_emitted_logs().pop().unwrap();
+        assert_eq!(topics[0], OperationQueued::SIGNATURE_HASH);
+        assert_eq!(topics[1], operation_id);
+        let eta = U256::from(1_000 + delay);
+        assert_eq!(contract.get_operation_eta(OP_ADD_BRIDGE_ADAPTER, adapter_value), eta);
+
+        vm.set_block_timestamp(1_000 + delay - 1);
+        assert!(matches!(
+            contract.execute_operation(OP_ADD_BRIDGE_ADAPTER, adapter_value),
+            Err(SettlementError::OperationNotReady(_))
+        ));
+        vm.set_block_timestamp(1_000 + delay);
+        contract.execute_operation(OP_ADD_BRIDGE_ADAPTER, adapter_value).unwrap();
+        assert_eq!(contract.get_bridge_adapters(), vec![adapter]);
+        assert!(matches!(
+            contract.execute_operation(OP_ADD_BRIDGE_ADAPTER, adapter_value),
+            Err(SettlementError::OperationNotQueued(_))
+        ));
+
+        // A pauser can cancel anything queued
+        vm.set_sender(OWNER);
+        let new_owner = U256::from_be_slice(stranger.as_slice());
+        contract.queue_operation(OP_TRANSFER_OWNERSHIP, new_owner).unwrap();
+        contract.access_control.grant_role(PAUSER_ROLE, manager).unwrap();
+        vm.set_sender(manager);
+        contract.cancel_operation(OP_TRANSFER_OWNERSHIP, new_owner).unwrap();
+        vm.set_sender(OWNER);
+        vm.set_block_timestamp(1_000 + 2 * delay);
+        assert!(matches!(
+            contract.execute_operation(OP_TRANSFER_OWNERSHIP, new_owner),
+            Err(SettlementError::OperationNotQueued(_))
+        ));
+        assert!(matches!(
+            contract.queue_operation(12, U256::ZERO),
+            Err(SettlementError::InvalidOperation(_))
+        ));
+
+        // Ownership moves through the queue
+        contract.queue_operation(OP_TRANSFER_OWNERSHIP, new_owner).unwrap();
+        vm.set_block_timestamp(1_000 + 3 * delay);
+        contract.execute_operation(OP_TRANSFER_OWNERSHIP, new_owner).unwrap();
+        assert_eq!(contract.ownable.get_pending_owner(), stranger);
+
+        let weth = Address::repeat_byte(0x40);
+        let weth_value = U256::from_be_slice(weth.as_slice());
+        contract.queue_operation(OP_SET_WETH, weth_value).unwrap();
+        vm.set_block_timestamp(1_000 + 4 * delay);
+        contract.execute_operation(OP_SET_WETH, weth_value).unwrap();
+        assert_eq!(contract.get_weth(), weth);
+
+        // Renouncing through the queue drops the owner's admin role too
+        contract.queue_operation(OP_RENOUNCE_OWNERSHIP, U256::ZERO).unwrap();
+        vm.set_block_timestamp(1_000 + 5 * delay);
+        contract.execute_operation(OP_RENOUNCE_OWNERSHIP, U256::ZERO).unwrap();
+        assert!(!contract.access_control.has_role(ADMIN_ROLE, OWNER));
+    }
+
+    #[test]
+    fn test_reentrant_calls_are_rejected() {
+        let (vm, mut contract) = setup_routes();
+        let recipient = Address::repeat_byte(0x20);
+        let execute = |contract: &mut SettlementSwitch| {
+            contract.execute_best_route(
+                U256::from(137),
+                TOKEN,
+                U256::from(AMOUNT),
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+
+        // As if an adapter called back while the router is mid-execution
+        contract.reentrancy_guard.enter().unwrap();
+        assert!(matches!(execute(&mut contract), Err(SettlementError::ReentrantCall(_))));
+        assert!(matches!(
+            contract.claim_refund(B256::ZERO),
+            Err(SettlementError::ReentrantCall(_))
+        ));
+        assert!(matches!(
+            contract.set_value_of_time(U256::from(1)),
+            Err(SettlementError::ReentrantCall(_))
+        ));
+        assert!(matches!(
+            contract.grant_role(PAUSER_ROLE, recipient),
+            Err(SettlementError::ReentrantCall(_))
+        ));
+        contract.reentrancy_guard.exit();
+
+        // A failing call releases the lock
+        vm.set_sender(Address::repeat_byte(0xBB));
+        assert!(contract.set_value_of_time(U256::from(1)).is_err());
+        vm.set_sender(OWNER);
+        execute(&mut contract).unwrap();
+        assert_eq!(contract.get_total_transfers(), U256::from(1));
+    }
+
+    #[test]
+    fn test_rate_limits_cap_volume_and_report_capacity() {
+        let (vm, mut contract) = setup_routes();
+        let user = Address::repeat_byte(0x21);
+        let amount = U256::from(AMOUNT);
+        let execute = |contract: &mut SettlementSwitch| {
+            contract.execute_with_adapter(
+                CHEAP,
+                U256::from(137),
+                TOKEN,
+                amount,
+                Address::repeat_byte(0x20),
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+        vm.set_block_timestamp(1_000);
+
+        vm.set_sender(user);
+        let limited = contract.set_rate_limits(TOKEN, amount, amount, amount, amount);
+        assert!(matches!(limited, Err(SettlementError::NotOwner(_))));
+        assert_eq!(contract.get_transfer_capacity(TOKEN, user), U256::MAX);
+
+        // Single transfers above the maximum are rejected outright
+        vm.set_sender(OWNER);
+        let half = amount / U256::from(2);
+        contract.set_rate_limits(TOKEN, half, U256::ZERO, U256::ZERO, U256::ZERO).unwrap();
+        vm.set_sender(user);
+        match execute(&mut contract) {
+            Err(SettlementError::TransferLimitExceeded(err)) => assert_eq!(err.maxAmount, half),
+            other => panic!("expected TransferLimitExceeded, got {other:?}"),
+        }
+
+        // The hourly window reports what is left and refills over the hour
+        let hourly = amount * U256::from(3) / U256::from(2);
+        vm.set_sender(OWNER);
+        contract.set_rate_limits(TOKEN, U256::ZERO, hourly, U256::ZERO, U256::ZERO).unwrap();
+        vm.set_sender(user);
+        execute(&mut contract).unwrap();
+        assert_eq!(
+            contract.get_rate_limit_capacity(TOKEN, user),
+            (U256::MAX, half, U256::MAX, U256::MAX)
+        );
+        match execute(&mut contract) {
+            Err(SettlementError::RateLimitExceeded(err)) => {
+                assert_eq!(err.window, U256::from(HOUR));
+                assert_eq!(err.remaining, half);
+            }
+            other => panic!("expected RateLimitExceeded, got {other:?}"),
+        }
+        vm.set_block_timestamp(1_000 + HOUR / 2);
+        assert_eq!(contract.get_transfer_capacity(TOKEN, user), half + hourly / U256::from(2));
+        execute(&mut contract).unwrap();
+
+        // The per-user daily limit leaves other senders alone
+        vm.set_sender(OWNER);
+        contract.set_rate_limits(TOKEN, U256::ZERO, U256::ZERO, U256::ZERO, amount).unwrap();
+        vm.set_sender(user);
+        execute(&mut contract).unwrap();
+        match execute(&mut contract) {
+            Err(SettlementError::UserRateLimitExceeded(err)) => {
+                assert_eq!((err.user, err.remaining), (user, U256::ZERO));
+            }
+            other => panic!("expected UserRateLimitExceeded, got {other:?}"),
+        }
+        vm.set_sender(Address::repeat_byte(0x22));
+        execute(&mut contract).unwrap();
+        assert_eq!(contract.get_transfer_capacity(TOKEN, user), U256::ZERO);
+    }
+
+    #[test]
+    fn test_exposure_caps_and_failures_steer_routing() {
+        let (vm, mut contract) = setup_routes();
+        let relayer = Address::repeat_byte(0xDD);
+        let recipient = Address::repeat_byte(0x20);
+        let (to_chain, amount, weight) = (U256::from(137), U256::from(AMOUNT), U256::from(5_000));
+        let best = |contract: &SettlementSwitch| {
+            let chain = U256::from(vm.chain_id());
+            let route = contract.get_best_route(
+                chain, to_chain, TOKEN, amount, recipient, weight, U256::ZERO, false, U256::ZERO,
+            );
+            route.unwrap().0
+        };
+        let execute = |contract: &mut SettlementSwitch| {
+            let nonce = contract.get_total_transfers();
+            contract
+                .execute_best_route(
+                    to_chain,
+                    TOKEN,
+                    amount,
+                    recipient,
+                    U256::ZERO,
+                    weight,
+                    U256::MAX,
+                    U256::ZERO,
+                    Bytes(vec![]),
+                    Address::ZERO,
+                    U256::ZERO,
+                )
+                .map(|_| contract.transfer_id(nonce))
+        };
+        vm.set_block_timestamp(1_000);
+
+        // $1,000 in flight through CHEAP leaves $500 under its $1,500 cap
+        let volume_usd = U256::from(100_000_000_000u64);
+        let cap_usd = U256::from(150_000_000_000u64);
+        contract.set_adapter_exposure_cap(CHEAP, cap_usd).unwrap();
+        assert_eq!(best(&contract), CHEAP);
+        let first = execute(&mut contract).unwrap();
+        assert_eq!(contract.get_adapter_exposure(CHEAP), (volume_usd, cap_usd));
+        assert_eq!(contract.get_transfer_status(first), TRANSFER_PENDING);
+
+        assert_eq!(best(&contract), FAST);
+        let direct = contract.execute_with_adapter(
+            CHEAP,
+            to_chain,
+            TOKEN,
+            amount,
+            recipient,
+            U256::ZERO,
+            U256::MAX,
+            U256::ZERO,
+            Bytes(vec![]),
+            Address::ZERO,
+            U256::ZERO,
+        );
+        match direct {
+            Err(SettlementError::ExposureCapExceeded(err)) => {
+                assert_eq!(err.remainingUsd, cap_usd - volume_usd);
+            }
+            other => panic!("expected ExposureCapExceeded, got {other:?}"),
+        }
+        let second = execute(&mut contract).unwrap();
+
+        // Only relayers report, once per transfer; settling frees the exposure
+        vm.set_sender(relayer);
+        let report = contract.report_transfer_status(first, TRANSFER_FAILED);
+        assert!(matches!(report, Err(SettlementError::MissingRole(_))));
+        vm.set_sender(OWNER);
+        contract.access_control.grant_role(RELAYER_ROLE, relayer).unwrap();
+        vm.set_sender(relayer);
+        contract.report_transfer_status(first, TRANSFER_FAILED).unwrap();
+        let report = contract.report_transfer_status(first, TRANSFER_COMPLETED);
+        assert!(matches!(report, Err(SettlementError::InvalidTransferStatus(_))));
+        let report = contract.report_transfer_status(second, TRANSFER_PENDING);
+        assert!(matches!(report, Err(SettlementError::InvalidTransferStatus(_))));
+        contract.report_transfer_status(second, TRANSFER_COMPLETED).unwrap();
+        assert_eq!(contract.get_adapter_exposure(CHEAP), (U256::ZERO, cap_usd));
+        assert_eq!(contract.get_transfer_status(first), TRANSFER_FAILED);
+        let health = |contract: &SettlementSwitch, adapter, health: (u64, u64, u64)| {
+            let expected = (U256::from(health.0), U256::from(health.1), U256::from(health.2));
+            assert_eq!(contract.get_adapter_health(adapter), expected);
+        };
+        health(&contract, CHEAP, (0, 1, 1));
+        health(&contract, FAST, (1, 0, 0));
+
+        // CHEAP scores 14% under FAST: one failure (+10%) keeps it ahead, two (+20%) do not
+        assert_eq!(best(&contract), CHEAP);
+        let third = execute(&mut contract).unwrap();
+        contract.report_transfer_status(third, TRANSFER_FAILED).unwrap();
+        health(&contract, CHEAP, (0, 2, 2));
+        assert_eq!(best(&contract), FAST);
+
+        // Failures stop counting as recent after a quiet day
+        vm.set_block_timestamp(1_000 + DAY + 1);
+        health(&contract, CHEAP, (0, 2, 0));
+        assert_eq!(best(&contract), CHEAP);
+    }
+
+    #[test]
+    fn test_screening_blocks_denied_and_sanctioned_addresses() {
+        let (vm, mut contract) = setup_routes();
+        let sanctions = Address::repeat_byte(0x50);
+        let (alice, bob, carol) = (
+            Address::repeat_byte(0x21),
+            Address::repeat_byte(0x22),
+            Address::repeat_byte(0x23),
+        );
+        let (to_chain, amount) = (U256::from(137), U256::from(AMOUNT));
+        let (chain, zero) = (U256::from(vm.chain_id()), U256::ZERO);
+        let routes = |contract: &SettlementSwitch, recipient: Address| {
+            contract.get_routes(chain, to_chain, TOKEN, amount, recipient, zero, zero, false, zero)
+        };
+        let execute = |contract: &mut SettlementSwitch, recipient: Address| {
+            contract.execute_with_adapter(
+                CHEAP,
+                to_chain,
+                TOKEN,
+                amount,
+                recipient,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+        };
+        let blocked = |result: Result<_, SettlementError>, account: Address| match result {
+            Err(SettlementError::AddressBlocked(err)) => assert_eq!(err.account, account),
+            Err(err) => panic!("expected AddressBlocked, got {err:?}"),
+            Ok(_) => panic!("expected AddressBlocked"),
+        };
+        let screen = |account: Address, result: Result<bool, Vec<u8>>| {
+            let call = ISanctionsList::isSanctionedCall { addr: account };
+            let result = result.map(|sanctioned| {
+                ISanctionsList::isSanctionedCall::abi_encode_returns(&(sanctioned,))
+            });
+            vm.mock_static_call(sanctions, call.abi_encode(), result);
+        };
+
+        // The denylist covers recipients in quotes and execution, and senders
+        vm.set_sender(alice);
+        assert!(matches!(contract.set_denied(bob, true), Err(SettlementError::NotOwner(_))));
+        vm.set_sender(OWNER);
+        contract.set_denied(bob, true).unwrap();
+        assert!(contract.is_denied(bob));
+        vm.set_sender(alice);
+        blocked(routes(&contract, bob).map(|_| ()), bob);
+        blocked(execute(&mut contract, bob).map(|_| ()), bob);
+        routes(&contract, carol).unwrap();
+        vm.set_sender(bob);
+        blocked(routes(&contract, carol).map(|_| ()), bob);
+        let split = contract.get_split_quote(chain, to_chain, TOKEN, amount, zero);
+        blocked(split.map(|_| ()), bob);
+        blocked(execute(&mut contract, carol).map(|_| ()), bob);
+
+        // The sanctions oracle is asked about the sender and the recipient
+        vm.set_sender(OWNER);
+        contract.set_denied(bob, false).unwrap();
+        contract.set_screening_oracle(sanctions).unwrap();
+        assert_eq!(contract.get_screening_oracle(), sanctions);
+        screen(alice, Ok(false));
+        screen(bob, Ok(false));
+        screen(carol, Ok(true));
+        vm.set_sender(alice);
+        blocked(execute(&mut contract, carol).map(|_| ()), carol);
+        vm.set_sender(carol);
+        blocked(execute(&mut contract, bob).map(|_| ()), carol);
+        vm.set_sender(alice);
+        execute(&mut contract, bob).unwrap();
+
+        // A reverting oracle blocks everyone
+        screen(alice, Err(vec![0xff]));
+        match execute(&mut contract, bob) {
+            Err(SettlementError::ScreeningFailed(err)) => {
+                assert_eq!((err.oracle, err.account), (sanctions, alice));
+            }
+            other => panic!("expected ScreeningFailed, got {other:?}"),
+        }
+        assert!(matches!(routes(&contract, bob), Err(SettlementError::ScreeningFailed(_))));
+    }
+
+    /// Give `implementation` code that answers `proxiableUUID` with `uuid`
+    fn mock_implementation(vm: &TestVM, implementation: Address, uuid: B256) {
+        vm.set_code(implementation, vec![0xfe]);
+        vm.mock_static_call(
+            implementation,
+            IERC1822Proxiable::proxiableUUIDCall {}.abi_encode(),
+            Ok(IERC1822Proxiable::proxiableUUIDCall::abi_encode_returns(&(uuid,))),
+        );
+    }
+
+    #[test]
+    fn test_upgrade_preserves_storage() {
+        let (vm, mut contract) = setup_routes();
+        let (admin, relayer) = (Address::repeat_byte(0xAD), Address::repeat_byte(0xDD));
+        let next = Address::repeat_byte(0x60);
+        let recipient = Address::repeat_byte(0x20);
+        vm.set_block_timestamp(1_000);
+
+        // Populate fields across the current layout
+        contract.set_chain_paused(U256::from(10), true).unwrap();
+        contract.set_rate_limits(TOKEN, U256::ZERO, U256::MAX, U256::ZERO, U256::ZERO).unwrap();
+        contract.set_adapter_exposure_cap(FAST, U256::from(1)).unwrap();
+        contract.set_denied(Address::repeat_byte(0x66), true).unwrap();
+        contract.access_control.grant_role(ADMIN_ROLE, admin).unwrap();
+        contract.access_control.grant_role(RELAYER_ROLE, relayer).unwrap();
+        contract
+            .execute_best_route(
+                U256::from(137),
+                TOKEN,
+                U256::from(AMOUNT),
+                recipient,
+                U256::ZERO,
+                U256::ZERO,
+                U256::MAX,
+                U256::ZERO,
+                Bytes(vec![]),
+                Address::ZERO,
+                U256::ZERO,
+            )
+            .unwrap();
+        let transfer_id = contract.transfer_id(U256::ZERO);
+        contract.set_protocol_fee(U256::from(10)).unwrap();
+
+        // Only admins upgrade, and only to code that proxies the same slot
+        vm.set_sender(relayer);
+        let upgraded = contract.upgrade_to(next);
+        assert!(matches!(upgraded, Err(SettlementError::MissingRole(_))));
+        vm.set_sender(admin);
+        let upgraded = contract.upgrade_to(next);
+        assert!(matches!(upgraded, Err(SettlementError::InvalidImplementation(_))));
+        mock_implementation(&vm, next, B256::repeat_byte(0x01));
+        let upgraded = contract.upgrade_to(next);
+        assert!(matches!(upgraded, Err(SettlementError::InvalidImplementation(_))));
+        mock_implementation(&vm, next, erc1967::IMPLEMENTATION_SLOT);
+
+        // With a delay set, upgrades go through the timelock
+        vm.set_sender(OWNER);
+        contract.set_timelock_delay(U256::from(3_600)).unwrap();
+        let before = vm.snapshot().storage;
+        vm.set_sender(admin);
+        let upgraded = contract.upgrade_to(next);
+        assert!(matches!(upgraded, Err(SettlementError::TimelockRequired(_))));
+        let value = U256::from_be_bytes(next.into_word().0);
+        contract.queue_operation(OP_UPGRADE_TO, value).unwrap();
+        vm.set_block_timestamp(1_000 + 3_600);
+        contract.execute_operation(OP_UPGRADE_TO, value).unwrap();
+        let (topics, _) = vm.get_emitted_logs().into_iter().rev().nth(1).unwrap();
+        assert_eq!(topics[0], Upgraded::SIGNATURE_HASH);
+        assert_eq!(topics[1], next.into_word());
+
+        // Only the implementation slot and the spent operation changed
+        let mut after = vm.snapshot().storage;
+        let slot = U256::from_be_bytes(erc1967::IMPLEMENTATION_SLOT.0);
+        assert_eq!(after.remove(&slot), Some(next.into_word()));
+        let changed: Vec<_> =
+            after.iter().filter(|(key, value)| before.get(key) != Some(value)).collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(*changed[0].1, B256::ZERO);
+        assert!(before.keys().all(|key| after.contains_key(key)));
+
+        // The new code reads the same state through the proxy's storage
+        let upgraded = SettlementSwitch::from(&vm);
+        assert_eq!(upgraded.get_implementation(), next);
+        assert_eq!(upgraded.get_storage_version(), U256::from(STORAGE_VERSION));
+        assert_eq!(upgraded.ownable.get_owner(), OWNER);
+        assert!(upgraded.access_control.has_role(ADMIN_ROLE, admin));
+        assert_eq!(upgraded.get_bridge_adapters(), vec![CHEAP, FAST]);
+        assert_eq!(upgraded.get_supported_tokens(), vec![TOKEN]);
+        assert_eq!(upgraded.get_protocol_fee(), U256::from(10));
+        assert!(upgraded.is_chain_paused(U256::from(10)));
+        assert_eq!(upgraded.get_rate_limits(TOKEN).1, U256::MAX);
+        assert_eq!(upgraded.get_adapter_exposure(FAST).1, U256::from(1));
+        assert!(upgraded.is_denied(Address::repeat_byte(0x66)));
+        assert_eq!(upgraded.get_total_transfers(), U256::from(1));
+        assert_eq!(upgraded.get_transfer_status(transfer_id), TRANSFER_PENDING);
+        assert_eq!(upgraded.get_timelock_delay(), U256::from(3_600));
+        assert_eq!(upgraded.proxiable_uuid(), erc1967::IMPLEMENTATION_SLOT);
+    }
+
+    #[test]
+    fn test_migrate_from_unversioned_layout() {
+        // A baseline proxy only wrote its own fields: price_oracle, adapter_count,
+        // total_transfers, total_volume_usd and owner, in slots 0 to 4
+        let vm = TestVM::default();
+        vm.set_storage(U256::ZERO, ORACLE.into_word());
+        vm.set_storage(U256::from(2), U256::from(7).into());
+        vm.set_storage(U256::from(4), OWNER.into_word());
+        let mut contract = SettlementSwitch::from(&vm);
+        assert_eq!(contract.ownable.get_owner(), OWNER);
+        assert_eq!(contract.get_storage_version(), U256::ZERO);
+
+        // Its owner stays in control and nobody can initialize over it
+        let stranger = Address::repeat_byte(0x21);
+        vm.set_sender(stranger);
+        assert!(matches!(
+            contract.initialize(stranger),
+            Err(SettlementError::AlreadyInitialized(_))
+        ));
+        assert!(matches!(contract.migrate(), Err(SettlementError::MissingRole(_))));
+        vm.set_sender(OWNER);
+        contract.set_value_of_time(U256::from(1)).unwrap();
+        contract.migrate().unwrap();
+        assert_eq!(contract.get_storage_version(), U256::from(STORAGE_VERSION));
+        assert_eq!(contract.get_total_transfers(), U256::from(7));
+        assert!(contract.access_control.has_role(ADMIN_ROLE, OWNER));
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = StorageMigrated::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!((event.fromVersion, event.toVersion), (U256::ZERO, U256::from(1)));
+
+        // Current storage needs nothing; storage from newer code is refused
+        let logs = vm.get_emitted_logs().len();
+        contract.migrate().unwrap();
+        assert_eq!(vm.get_emitted_logs().len(), logs);
+        contract.storage_version.set(U256::from(STORAGE_VERSION + 1));
+        assert!(matches!(contract.migrate(), Err(SettlementError::InvalidStorageVersion(_))));
+
+        // Migrated storage is initialized even once the owner renounces
+        contract.renounce_ownership().unwrap();
+        assert!(matches!(
+            contract.initialize(ORACLE),
+            Err(SettlementError::AlreadyInitialized(_))
+        ));
+    }
+
+    #[test]
+    fn test_only_owner_can_set_oracle() {
+        let (vm, mut contract) = setup();
+        vm.set_sender(Address::repeat_byte(0xBB));
+        assert!(contract.set_price_oracle(Address::repeat_byte(0x02)).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use stylus_sdk::{
     alloc::vec::Vec,
     evm,
@@ -7,6 +7,12 @@ use stylus_sdk::{
     storage::StorageMap,
 };
 use crate::chainlink::ChainlinkPriceFeed;
+use crate::errors::{
+    GasPriceNotSet, InvalidChainId, InvalidGasPrice, InvalidPriceFeed, InvalidToken,
+    PriceFeedNotSet, SettlementError,
+};
+use crate::access_control::{AccessControl, ORACLE_UPDATER_ROLE};
+use crate::ownable::Ownable;
 
 #[sol_event]
 pub struct TokenPriceFeedSet {
@@ -37,21 +43,27 @@ pub struct PriceOracle {
     // Gas prices mapping: chain_id => gas price
     gas_prices: StorageMap<U256, U256>,
     
-    // Owner address
-    owner: Address,
+    // Owner and pending owner
+    #[borrow]
+    ownable: Ownable,
+
+    // Role members; the deployer starts as admin
+    #[borrow]
+    access_control: AccessControl,
 }
 
 impl PriceOracle {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
-        instance
+        instance.ownable.init(msg::sender())?;
+        instance.access_control.init(msg::sender());
+        Ok(instance)
     }
 
-    pub fn set_token_price_feed(&mut self, token: Address, price_feed: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(token != Address::ZERO, "Invalid token address");
-        ensure!(price_feed != Address::ZERO, "Invalid price feed");
+    pub fn set_token_price_feed(&mut self, token: Address, price_feed: Address) -> Result<(), SettlementError> {
+        self.ownable.only_owner()?;
+        ensure!(token != Address::ZERO, InvalidToken {});
+        ensure!(price_feed != Address::ZERO, InvalidPriceFeed {});
         
         self.token_price_feeds.insert(token, price_feed);
         
@@ -60,10 +72,10 @@ impl PriceOracle {
         Ok(())
     }
 
-    pub fn set_native_price_feed(&mut self, chain_id: U256, price_feed: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(!chain_id.is_zero(), "Invalid chain ID");
-        ensure!(price_feed != Address::ZERO, "Invalid price feed");
+    pub fn set_native_price_feed(&mut self, chain_id: U256, price_feed: Address) -> Result<(), SettlementError> {
+        self.ownable.only_owner()?;
+        ensure!(!chain_id.is_zero(), InvalidChainId {});
+        ensure!(price_feed != Address::ZERO, InvalidPriceFeed {});
         
         self.native_price_feeds.insert(chain_id, price_feed);
         
@@ -72,10 +84,12 @@ impl PriceOracle {
         Ok(())
     }
 
-    pub fn set_gas_price(&mut self, chain_id: U256, gas_price: U256) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(!chain_id.is_zero(), "Invalid chain ID");
-        ensure!(!gas_price.is_zero(), "Invalid gas price");
+    /// Push the gas price for `chain_id`. Oracle updaters can call this
+    /// without the owner key.
+    pub fn set_gas_price(&mut self, chain_id: U256, gas_price: U256) -> Result<(), SettlementError> {
+        self.ensure_role(ORACLE_UPDATER_ROLE)?;
+        ensure!(!chain_id.is_zero(), InvalidChainId {});
+        ensure!(!gas_price.is_zero(), InvalidGasPrice {});
         
         self.gas_prices.insert(chain_id, gas_price);
         
@@ -84,49 +98,52 @@ impl PriceOracle {
         Ok(())
     }
 
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        Ok(())
+    /// The owner passes every role check
+    fn ensure_role(&self, role: B256) -> Result<(), SettlementError> {
+        if self.ownable.only_owner().is_ok() {
+            return Ok(());
+        }
+        self.access_control.only_role(role)
     }
 
-    fn get_price_feed(&self, token: Address) -> Result<ChainlinkPriceFeed, Vec<u8>> {
+    fn get_price_feed(&self, token: Address) -> Result<ChainlinkPriceFeed, SettlementError> {
         let feed_address = self.token_price_feeds
             .get(&token)
             .copied()
-            .ok_or_else(|| "Price feed not set".as_bytes().to_vec())?;
+            .ok_or_else(|| SettlementError::from(PriceFeedNotSet {}))?;
             
         Ok(ChainlinkPriceFeed::new(feed_address))
     }
 
-    fn get_native_price_feed(&self, chain_id: U256) -> Result<ChainlinkPriceFeed, Vec<u8>> {
+    fn get_native_price_feed(&self, chain_id: U256) -> Result<ChainlinkPriceFeed, SettlementError> {
         let feed_address = self.native_price_feeds
             .get(&chain_id)
             .copied()
-            .ok_or_else(|| "Price feed not set".as_bytes().to_vec())?;
+            .ok_or_else(|| SettlementError::from(PriceFeedNotSet {}))?;
             
         Ok(ChainlinkPriceFeed::new(feed_address))
     }
 }
 
 impl super::IPriceOracle for PriceOracle {
-    fn get_token_price(&self, token: Address) -> Result<U256, Vec<u8>> {
+    fn get_token_price(&self, token: Address) -> Result<U256, SettlementError> {
         let price_feed = self.get_price_feed(token)?;
         price_feed.get_price()
     }
 
-    fn get_gas_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    fn get_gas_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         self.gas_prices
             .get(&chain_id)
-            .ok_or_else(|| "Gas price not set".as_bytes().to_vec())
+            .ok_or_else(|| GasPriceNotSet { chainId: chain_id }.into())
             .copied()
     }
 
-    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         let price_feed = self.get_native_price_feed(chain_id)?;
         price_feed.get_price()
     }
 
-    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, Vec<u8>> {
+    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, SettlementError> {
         let gas_price = self.get_gas_price(chain_id)?;
         let native_price = self.get_native_token_price(chain_id)?;
         
@@ -141,19 +158,19 @@ impl super::IPriceOracle for PriceOracle {
 // Public external interface to match router sol_interface expectations
 #[public]
 impl PriceOracle {
-    pub fn get_token_price(&self, token: Address) -> Result<U256, Vec<u8>> {
+    pub fn get_token_price(&self, token: Address) -> Result<U256, SettlementError> {
         <Self as super::IPriceOracle>::get_token_price(self, token)
     }
 
-    pub fn get_gas_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    pub fn get_gas_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         <Self as super::IPriceOracle>::get_gas_price(self, chain_id)
     }
 
-    pub fn get_native_token_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    pub fn get_native_token_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         <Self as super::IPriceOracle>::get_native_token_price(self, chain_id)
     }
 
-    pub fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, Vec<u8>> {
+    pub fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, SettlementError> {
         <Self as super::IPriceOracle>::calculate_gas_cost(self, chain_id, gas_amount)
     }
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+//! Two-step ownership shared by every contract in the crate.
+//!
+//! A contract embeds `Ownable` as a `#[borrow]` storage field, exposes its
+//! methods with `#[inherit(Ownable)]` and gates owner-only methods with
+//! `only_owner`.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{alloy_primitives::Address, prelude::*};
+
+use crate::errors::{AlreadyInitialized, NotOwner, NotPendingOwner, SettlementError};
+use crate::events::{OwnershipTransferStarted, OwnershipTransferred};
+
+sol_storage! {
+    pub struct Ownable {
+        address owner;
+        // Proposed by `transfer_ownership`; takes over on `accept_ownership`
+        address pending_owner;
+        // Set by `init`; renouncing ownership does not allow initializing again.
+        // Storage from before this flag counts as initialized while it has an owner.
+        bool initialized;
+    }
+}
+
+#[public]
+impl Ownable {
+    /// Get the owner. Zero once ownership is renounced.
+    #[allow(non_snake_case)]
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Get the proposed owner that has not accepted yet
+    #[allow(non_snake_case)]
+    pub fn get_pending_owner(&self) -> Address {
+        self.pending_owner.get()
+    }
+
+    /// Propose `new_owner`, who takes over by calling `accept_ownership`. The
+    /// current owner keeps control until then; zero cancels the proposal.
+    #[allow(non_snake_case)]
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), SettlementError> {
+        self.only_owner()?;
+
+        self.pending_owner.set(new_owner);
+
+        log(self.vm(), OwnershipTransferStarted {
+            previousOwner: self.owner.get(),
+            newOwner: new_owner,
+        });
+
+        Ok(())
+    }
+
+    /// Become the owner. Only the pending owner can accept.
+    #[allow(non_snake_case)]
+    pub fn accept_ownership(&mut self) -> Result<(), SettlementError> {
+        let caller = self.vm().msg_sender();
+        if caller == Address::ZERO || caller != self.pending_owner.get() {
+            return Err(NotPendingOwner { caller }.into());
+        }
+
+        self.pending_owner.set(Address::ZERO);
+        self.set_owner(caller);
+
+        Ok(())
+    }
+
+    /// Give up ownership for good. Owner-only methods can no longer be called.
+    #[allow(non_snake_case)]
+    pub fn renounce_ownership(&mut self) -> Result<(), SettlementError> {
+        self.only_owner()?;
+
+        self.pending_owner.set(Address::ZERO);
+        self.set_owner(Address::ZERO);
+
+        Ok(())
+    }
+}
+
+impl Ownable {
+    /// Make `owner` the first owner. Fails if the contract was initialized before.
+    pub fn init(&mut self, owner: Address) -> Result<(), SettlementError> {
+        if self.initialized.get() || self.owner.get() != Address::ZERO {
+            return Err(AlreadyInitialized {}.into());
+        }
+        self.initialized.set(true);
+        self.set_owner(owner);
+        Ok(())
+    }
+
+    /// Set `initialized` on storage written before it existed, if it has an owner
+    pub fn migrate_initialized(&mut self) {
+        if self.owner.get() != Address::ZERO {
+            self.initialized.set(true);
+        }
+    }
+
+    pub fn only_owner(&self) -> Result<(), SettlementError> {
+        let owner = self.owner.get();
+        if owner == Address::ZERO || self.vm().msg_sender() != owner {
+            return Err(NotOwner {}.into());
+        }
+        Ok(())
+    }
+
+    fn set_owner(&mut self, owner: Address) {
+        let previous = self.owner.get();
+        self.owner.set(owner);
+
+        log(self.vm(), OwnershipTransferred {
+            previousOwner: previous,
+            newOwner: owner,
+        });
+    }
+}
@@ -1,6 +1,13 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use stylus_sdk::{prelude::*, storage::StorageMap};
 
+use crate::errors::{
+    GasPriceNotSet, InvalidChainId, InvalidGasPrice, InvalidPriceFeed, InvalidToken,
+    PriceFeedNotSet, SettlementError,
+};
+use crate::access_control::{AccessControl, ORACLE_UPDATER_ROLE};
+use crate::ownable::Ownable;
+
 #[storage]
 pub struct PriceOracle {
     // Token price feeds mapping: token address => price feed address
@@ -12,98 +19,109 @@ pub struct PriceOracle {
     // Gas prices mapping: chain_id => gas price
     gas_prices: StorageMap<U256, U256>,
     
-    // Owner address
-    owner: Address,
+    // Owner and pending owner
+    #[borrow]
+    ownable: Ownable,
+
+    // Role members; the deployer starts as admin
+    #[borrow]
+    access_control: AccessControl,
 }
 
 /// Price Oracle trait defining the interface for price and gas calculations
 pub trait IPriceOracle {
     /// Get the USD price of a token
     /// Returns price in USD (8 decimals)
-    fn get_token_price(&self, token: Address) -> Result<U256, Vec<u8>>;
+    fn get_token_price(&self, token: Address) -> Result<U256, SettlementError>;
 
     /// Get the gas price for a specific chain
     /// Returns gas price in wei
-    fn get_gas_price(&self, chain_id: U256) -> Result<U256, Vec<u8>>;
+    fn get_gas_price(&self, chain_id: U256) -> Result<U256, SettlementError>;
 
     /// Get the native token price in USD for a chain
     /// Returns price in USD (8 decimals)
-    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, Vec<u8>>;
+    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, SettlementError>;
 
     /// Calculate the USD cost of gas for a transaction
     /// Returns cost in USD (8 decimals)
-    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, Vec<u8>>;
+    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, SettlementError>;
 }
 
 impl PriceOracle {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, SettlementError> {
         let mut instance = Self::default();
-        instance.owner = msg::sender();
-        instance
+        instance.ownable.init(msg::sender())?;
+        instance.access_control.init(msg::sender());
+        Ok(instance)
     }
 
-    pub fn set_token_price_feed(&mut self, token: Address, price_feed: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(token != Address::ZERO, "Invalid token address");
-        ensure!(price_feed != Address::ZERO, "Invalid price feed");
+    pub fn set_token_price_feed(&mut self, token: Address, price_feed: Address) -> Result<(), SettlementError> {
+        self.ownable.only_owner()?;
+        ensure!(token != Address::ZERO, InvalidToken {});
+        ensure!(price_feed != Address::ZERO, InvalidPriceFeed {});
         
         self.token_price_feeds.insert(token, price_feed);
         Ok(())
     }
 
-    pub fn set_native_price_feed(&mut self, chain_id: U256, price_feed: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(!chain_id.is_zero(), "Invalid chain ID");
-        ensure!(price_feed != Address::ZERO, "Invalid price feed");
+    pub fn set_native_price_feed(&mut self, chain_id: U256, price_feed: Address) -> Result<(), SettlementError> {
+        self.ownable.only_owner()?;
+        ensure!(!chain_id.is_zero(), InvalidChainId {});
+        ensure!(price_feed != Address::ZERO, InvalidPriceFeed {});
         
         self.native_price_feeds.insert(chain_id, price_feed);
         Ok(())
     }
 
-    pub fn set_gas_price(&mut self, chain_id: U256, gas_price: U256) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(!chain_id.is_zero(), "Invalid chain ID");
-        ensure!(!gas_price.is_zero(), "Invalid gas price");
+    /// Push the gas price for `chain_id`. Oracle updaters can call this
+    /// without the owner key.
+    pub fn set_gas_price(&mut self, chain_id: U256, gas_price: U256) -> Result<(), SettlementError> {
+        self.ensure_role(ORACLE_UPDATER_ROLE)?;
+        ensure!(!chain_id.is_zero(), InvalidChainId {});
+        ensure!(!gas_price.is_zero(), InvalidGasPrice {});
         
         self.gas_prices.insert(chain_id, gas_price);
         Ok(())
     }
 
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        Ok(())
+    /// The owner passes every role check
+    fn ensure_role(&self, role: B256) -> Result<(), SettlementError> {
+        if self.ownable.only_owner().is_ok() {
+            return Ok(());
+        }
+        self.access_control.only_role(role)
     }
 }
 
 impl IPriceOracle for PriceOracle {
-    fn get_token_price(&self, token: Address) -> Result<U256, Vec<u8>> {
+    fn get_token_price(&self, token: Address) -> Result<U256, SettlementError> {
         let price_feed = self.token_price_feeds
             .get(&token)
-            .ok_or_else(|| "Price feed not set".as_bytes().to_vec())?;
+            .ok_or_else(|| SettlementError::from(PriceFeedNotSet {}))?;
             
         // Here we would integrate with Chainlink price feeds
         // For now returning a placeholder
         Ok(U256::from(1_000_000_000)) // $1.00 with 8 decimals
     }
 
-    fn get_gas_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    fn get_gas_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         self.gas_prices
             .get(&chain_id)
-            .ok_or_else(|| "Gas price not set".as_bytes().to_vec())
+            .ok_or_else(|| GasPriceNotSet { chainId: chain_id }.into())
             .copied()
     }
 
-    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, Vec<u8>> {
+    fn get_native_token_price(&self, chain_id: U256) -> Result<U256, SettlementError> {
         let price_feed = self.native_price_feeds
             .get(&chain_id)
-            .ok_or_else(|| "Price feed not set".as_bytes().to_vec())?;
+            .ok_or_else(|| SettlementError::from(PriceFeedNotSet {}))?;
             
         // Here we would integrate with Chainlink price feeds
         // For now returning a placeholder
         Ok(U256::from(2_000_000_000)) // $2.00 with 8 decimals
     }
 
-    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, Vec<u8>> {
+    fn calculate_gas_cost(&self, chain_id: U256, gas_amount: U256) -> Result<U256, SettlementError> {
         let gas_price = self.get_gas_price(chain_id)?;
         let native_price = self.get_native_token_price(chain_id)?;
         
@@ -0,0 +1,52 @@
+//! Rolling-window usage buckets for rate limits.
+//!
+//! A bucket's usage drains at `limit / window` per second, so it tracks the
+//! amount used over roughly the last `window` seconds without storing history.
+//! A zero limit means unlimited; usage is not tracked until a limit is set.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+/// Window lengths in seconds
+pub const HOUR: u64 = 60 * 60;
+pub const DAY: u64 = 24 * HOUR;
+
+sol_storage! {
+    pub struct RateBucket {
+        uint256 used;
+        uint256 updated_at;
+    }
+}
+
+impl RateBucket {
+    /// Usage left at `now` after draining since the last update
+    pub fn used(&self, limit: U256, window: u64, now: U256) -> U256 {
+        let elapsed = now.saturating_sub(self.updated_at.get());
+        let drained = limit.saturating_mul(elapsed) / U256::from(window);
+        self.used.get().saturating_sub(drained)
+    }
+
+    /// Capacity left under `limit` at `now`; `U256::MAX` when unlimited
+    pub fn remaining(&self, limit: U256, window: u64, now: U256) -> U256 {
+        if limit == U256::ZERO {
+            return U256::MAX;
+        }
+        limit.saturating_sub(self.used(limit, window, now))
+    }
+
+    /// Record `amount` used at `now`
+    pub fn consume(&mut self, amount: U256, limit: U256, window: u64, now: U256) {
+        if limit == U256::ZERO {
+            return;
+        }
+        let used = self.used(limit, window, now);
+        self.used.set(used.saturating_add(amount));
+        self.updated_at.set(now);
+    }
+
+    /// Give back `amount` consumed earlier in the same call
+    pub fn release(&mut self, amount: U256) {
+        let used = self.used.get();
+        self.used.set(used.saturating_sub(amount));
+    }
+}
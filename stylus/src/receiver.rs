@@ -0,0 +1,765 @@
+//! Destination-chain receiver.
+//!
+//! Bridges deliver funds here together with the settlement message from
+//! `crate::settlement` naming the source transfer; the receiver forwards the
+//! funds to the final recipient, hands it any payload, and records the
+//! transfer as settled.
+//!
+//! Only the configured bridge endpoints may call back. Stargate also reports
+//! the contract that sent the message, and only (source chain, sender) pairs
+//! the owner trusts can settle through it. Across does not report the
+//! depositor, so anyone can deposit a message naming any transfer: Across
+//! settlements are provisional, and a later delivery of the same transfer
+//! replaces them instead of being rejected.
+//!
+//! CCTP mints USDC without calling back, so the source burns with this receiver
+//! as mint recipient and destination caller and the message as hook data, and
+//! anyone relays the attested burn through `relay_cctp_burn`, crediting the
+//! transfer with what it minted. The message sent alongside settles that
+//! credit when CCTP delivers it to `handleReceiveMessage`. Both the depositor
+//! and the message sender must be trusted (domain, sender) pairs.
+
+use alloc::{vec, vec::Vec};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    crypto::keccak,
+    prelude::*,
+};
+
+use crate::errors::{
+    BridgeNotConfigured, ExternalCallFailed, InvalidAddress, InvalidSettlementMessage,
+    SettlementError, SettlementNotFunded, TransferAlreadySettled, UnauthorizedCaller,
+    UntrustedSource,
+};
+use crate::events::{ReceiverEndpointsUpdated, TransferSettled, TrustedSourceUpdated};
+use crate::interfaces::{
+    call_contract, call_token, send_native, IMessageTransmitter, ISettlementRecipient, IERC20,
+};
+use crate::ownable::Ownable;
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
+use crate::settlement::{decode_message, SettlementMessage};
+use crate::NATIVE_TOKEN;
+
+/// Bridge that delivered a settlement, as reported in `TransferSettled`
+const BRIDGE_STARGATE: u8 = 1;
+const BRIDGE_ACROSS: u8 = 2;
+const BRIDGE_CCTP: u8 = 3;
+
+sol_storage! {
+    #[cfg_attr(feature = "receiver", entrypoint)]
+    pub struct SettlementReceiver {
+        #[borrow]
+        Ownable ownable;
+
+        // Contracts allowed to call each bridge callback
+        address stargate_router;
+        address across_spoke_pool;
+        address cctp_message_transmitter;
+
+        // Settled transfers by source transfer id
+        mapping(bytes32 => bool) settled;
+        mapping(bytes32 => address) settled_recipient;
+        mapping(bytes32 => address) settled_token;
+        mapping(bytes32 => uint256) settled_amount;
+        mapping(bytes32 => uint256) settled_at;
+
+        uint256 total_settled;
+
+        // Held while a settlement forwards funds to the recipient
+        ReentrancyGuard reentrancy_guard;
+
+        // Senders allowed to settle, keyed by bridge, source chain (Stargate chain
+        // id or CCTP domain) and sender
+        mapping(bytes32 => bool) trusted_sources;
+        // USDC the CCTP token messenger mints here
+        address cctp_token;
+        // Settled from a trusted source, so no later delivery can replace it
+        mapping(bytes32 => bool) settled_verified;
+        // CCTP token messenger on this chain, the only recipient of burn messages
+        address cctp_token_messenger;
+        // USDC minted by relayed CCTP burns and not yet settled, by transfer id
+        mapping(bytes32 => uint256) cctp_credited;
+    }
+}
+
+#[public]
+#[inherit(Ownable)]
+impl SettlementReceiver {
+    /// Initialize the receiver with the bridge endpoints and CCTP USDC on this chain
+    #[allow(non_snake_case)]
+    pub fn initialize(
+        &mut self,
+        stargate_router: Address,
+        across_spoke_pool: Address,
+        cctp_message_transmitter: Address,
+        cctp_token_messenger: Address,
+        cctp_token: Address,
+    ) -> Result<(), SettlementError> {
+        let owner = self.vm().msg_sender();
+        self.ownable.init(owner)?;
+
+        // Takes the reentrancy lock itself
+        self.set_endpoints(
+            stargate_router,
+            across_spoke_pool,
+            cctp_message_transmitter,
+            cctp_token_messenger,
+            cctp_token,
+        )
+    }
+
+    /// Set the bridge contracts allowed to deliver settlements, the CCTP
+    /// contracts burns are relayed through and the USDC that CCTP mints here.
+    /// Zero disables a bridge.
+    #[allow(non_snake_case)]
+    pub fn set_endpoints(
+        &mut self,
+        stargate_router: Address,
+        across_spoke_pool: Address,
+        cctp_message_transmitter: Address,
+        cctp_token_messenger: Address,
+        cctp_token: Address,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+
+            this.stargate_router.set(stargate_router);
+            this.across_spoke_pool.set(across_spoke_pool);
+            this.cctp_message_transmitter.set(cctp_message_transmitter);
+            this.cctp_token_messenger.set(cctp_token_messenger);
+            this.cctp_token.set(cctp_token);
+
+            log(this.vm(), ReceiverEndpointsUpdated {
+                stargateRouter: stargate_router,
+                acrossSpokePool: across_spoke_pool,
+                cctpMessageTransmitter: cctp_message_transmitter,
+                cctpTokenMessenger: cctp_token_messenger,
+                cctpToken: cctp_token,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Trust or distrust `source` on `source_chain` to send settlements over
+    /// `bridge`: the source router's Stargate adapter by Stargate chain id, or
+    /// its CCTP depositor by domain. Addresses are left-padded to 32 bytes.
+    #[allow(non_snake_case)]
+    pub fn set_trusted_source(
+        &mut self,
+        bridge: u8,
+        source_chain: u32,
+        source: B256,
+        trusted: bool,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+
+            let key = source_key(bridge, source_chain, source);
+            this.trusted_sources.setter(key).set(trusted);
+
+            log(this.vm(), TrustedSourceUpdated {
+                bridge,
+                sourceChain: source_chain,
+                source,
+                trusted,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Whether `source` on `source_chain` may send settlements over `bridge`
+    #[allow(non_snake_case)]
+    pub fn is_trusted_source(&self, bridge: u8, source_chain: u32, source: B256) -> bool {
+        self.trusted_sources.get(source_key(bridge, source_chain, source))
+    }
+
+    /// Stargate callback: `amount_ld` of `token` has arrived with `payload`, sent
+    /// by `src_address` (20 bytes) on `src_chain_id`
+    #[selector(name = "sgReceive")]
+    pub fn sg_receive(
+        &mut self,
+        src_chain_id: u16,
+        src_address: Bytes,
+        _nonce: U256,
+        token: Address,
+        amount_ld: U256,
+        payload: Bytes,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_caller(this.stargate_router.get())?;
+            let source = Address::try_from(src_address.as_slice())
+                .map_or(B256::ZERO, |source| source.into_word());
+            this.ensure_trusted(BRIDGE_STARGATE, u32::from(src_chain_id), source)?;
+            let message = decode_message(&payload)?;
+            this.settle(&message, token, amount_ld, BRIDGE_STARGATE, true)
+        })
+    }
+
+    /// Across callback: a relayer filled `amount` of `token_sent` with `message`.
+    /// Across does not report the depositor, so the settlement stays provisional.
+    #[selector(name = "handleV3AcrossMessage")]
+    pub fn handle_v3_across_message(
+        &mut self,
+        token_sent: Address,
+        amount: U256,
+        _relayer: Address,
+        message: Bytes,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_caller(this.across_spoke_pool.get())?;
+            let message = decode_message(&message)?;
+            this.settle(&message, token_sent, amount, BRIDGE_ACROSS, false)
+        })
+    }
+
+    /// Relay an attested CCTP burn `message` to the message transmitter, which
+    /// mints its USDC here, and credit the transfer in its hook data with the
+    /// amount minted for this burn, net of the fee CCTP executed
+    #[allow(non_snake_case)]
+    pub fn relay_cctp_burn(
+        &mut self,
+        message: Bytes,
+        attestation: Bytes,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            let transmitter = this.cctp_message_transmitter.get();
+            let token = this.cctp_token.get();
+            if transmitter == Address::ZERO || token == Address::ZERO {
+                return Err(BridgeNotConfigured { token }.into());
+            }
+
+            let burn = CctpBurn::parse(&message).ok_or(InvalidSettlementMessage {})?;
+            if burn.recipient != this.cctp_token_messenger.get().into_word()
+                || burn.mint_recipient != this.vm().contract_address().into_word()
+            {
+                return Err(InvalidSettlementMessage {}.into());
+            }
+            this.ensure_trusted(BRIDGE_CCTP, burn.source_domain, burn.depositor)?;
+            let transfer_id = decode_message(burn.hook_data)?.transfer_id;
+            let minted_amount = burn.amount.saturating_sub(burn.fee_executed);
+
+            let relay = IMessageTransmitter::receiveMessageCall {
+                message: message.0.into(),
+                attestation: attestation.0.into(),
+            };
+            let minted = call_contract(this.vm(), transmitter, relay)?._0;
+            if !minted {
+                return Err(ExternalCallFailed {
+                    reason: Vec::new().into(),
+                }
+                .into());
+            }
+
+            let credited = this.cctp_credited.get(transfer_id);
+            this.cctp_credited.setter(transfer_id).set(credited + minted_amount);
+            Ok(())
+        })
+    }
+
+    /// CCTP hook: the message transmitter relays `message_body` from `sender` on
+    /// `source_domain`. It settles the USDC that `relay_cctp_burn` credited to
+    /// the transfer, which is what arrived rather than the message's amount.
+    #[selector(name = "handleReceiveMessage")]
+    pub fn handle_receive_message(
+        &mut self,
+        source_domain: u32,
+        sender: B256,
+        message_body: Bytes,
+    ) -> Result<bool, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_caller(this.cctp_message_transmitter.get())?;
+            this.ensure_trusted(BRIDGE_CCTP, source_domain, sender)?;
+            let message = decode_message(&message_body)?;
+            let transfer_id = message.transfer_id;
+
+            let token = this.cctp_token.get();
+            if token == Address::ZERO {
+                return Err(BridgeNotConfigured { token }.into());
+            }
+            let credited = this.cctp_credited.get(transfer_id);
+            if credited == U256::ZERO {
+                return Err(SettlementNotFunded {
+                    transferId: transfer_id,
+                    amount: message.amount,
+                }
+                .into());
+            }
+
+            this.cctp_credited.setter(transfer_id).set(U256::ZERO);
+            this.settle(&message, token, credited, BRIDGE_CCTP, true)?;
+            Ok(true)
+        })
+    }
+
+    /// Get the USDC relayed CCTP burns have minted for `transfer_id` and its
+    /// message has not settled yet
+    #[allow(non_snake_case)]
+    pub fn get_cctp_credit(&self, transfer_id: B256) -> U256 {
+        self.cctp_credited.get(transfer_id)
+    }
+
+    /// Whether the source transfer `transfer_id` has been settled here:
+    /// (settled, verified). Unverified (Across) settlements are provisional.
+    #[allow(non_snake_case)]
+    pub fn is_settled(&self, transfer_id: B256) -> (bool, bool) {
+        (
+            self.settled.get(transfer_id),
+            self.settled_verified.get(transfer_id),
+        )
+    }
+
+    /// Get a settlement: (recipient, token, amount, settled_at). Zero if not settled.
+    #[allow(non_snake_case)]
+    pub fn get_settlement(&self, transfer_id: B256) -> (Address, Address, U256, U256) {
+        (
+            self.settled_recipient.get(transfer_id),
+            self.settled_token.get(transfer_id),
+            self.settled_amount.get(transfer_id),
+            self.settled_at.get(transfer_id),
+        )
+    }
+
+    /// Get the number of settled transfers
+    #[allow(non_snake_case)]
+    pub fn get_total_settled(&self) -> U256 {
+        self.total_settled.get()
+    }
+
+    /// Get the bridge endpoints: (stargate_router, across_spoke_pool,
+    /// cctp_message_transmitter, cctp_token_messenger, cctp_token)
+    #[allow(non_snake_case)]
+    pub fn get_endpoints(&self) -> (Address, Address, Address, Address, Address) {
+        (
+            self.stargate_router.get(),
+            self.across_spoke_pool.get(),
+            self.cctp_message_transmitter.get(),
+            self.cctp_token_messenger.get(),
+            self.cctp_token.get(),
+        )
+    }
+
+    /// Accept native value delivered by bridges
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        Ok(())
+    }
+}
+
+impl NonReentrant for SettlementReceiver {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
+}
+
+impl SettlementReceiver {
+    fn ensure_caller(&self, endpoint: Address) -> Result<(), SettlementError> {
+        let caller = self.vm().msg_sender();
+        if endpoint == Address::ZERO || caller != endpoint {
+            return Err(UnauthorizedCaller { caller }.into());
+        }
+        Ok(())
+    }
+
+    fn ensure_trusted(
+        &self,
+        bridge: u8,
+        source_chain: u32,
+        source: B256,
+    ) -> Result<(), SettlementError> {
+        if !self.is_trusted_source(bridge, source_chain, source) {
+            return Err(UntrustedSource {
+                bridge,
+                sourceChain: source_chain,
+                source,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Record the settlement, then forward the funds, hand any payload to the
+    /// recipient's `onSettlement` and emit `TransferSettled`. A `verified`
+    /// settlement is final; an unverified one is replaced by the next delivery
+    /// of the same transfer.
+    fn settle(
+        &mut self,
+        message: &SettlementMessage,
+        token: Address,
+        amount: U256,
+        bridge: u8,
+        verified: bool,
+    ) -> Result<(), SettlementError> {
+        let (transfer_id, recipient) = (message.transfer_id, message.recipient);
+        if self.settled_verified.get(transfer_id) {
+            return Err(TransferAlreadySettled {
+                transferId: transfer_id,
+            }
+            .into());
+        }
+        if recipient == Address::ZERO {
+            return Err(InvalidAddress {}.into());
+        }
+
+        if !self.settled.get(transfer_id) {
+            let total = self.total_settled.get();
+            self.total_settled.set(total + U256::from(1));
+        }
+        let settled_at = U256::from(self.vm().block_timestamp());
+        self.settled.setter(transfer_id).set(true);
+        self.settled_verified.setter(transfer_id).set(verified);
+        self.settled_recipient.setter(transfer_id).set(recipient);
+        self.settled_token.setter(transfer_id).set(token);
+        self.settled_amount.setter(transfer_id).set(amount);
+        self.settled_at.setter(transfer_id).set(settled_at);
+
+        if token == NATIVE_TOKEN {
+            send_native(self.vm(), recipient, amount)?;
+        } else {
+            call_token(self.vm(), token, IERC20::transferCall {
+                to: recipient,
+                amount,
+            })?;
+        }
+        if !message.payload.is_empty() {
+            call_contract(self.vm(), recipient, ISettlementRecipient::onSettlementCall {
+                transferId: transfer_id,
+                token,
+                amount,
+                payload: message.payload.clone().into(),
+            })?;
+        }
+
+        log(self.vm(), TransferSettled {
+            transferId: transfer_id,
+            recipient,
+            token,
+            amount,
+            bridge,
+            verified,
+        });
+
+        Ok(())
+    }
+}
+
+/// Key of a trusted source in `trusted_sources`
+fn source_key(bridge: u8, source_chain: u32, source: B256) -> B256 {
+    keccak((U256::from(bridge), U256::from(source_chain), source).abi_encode())
+}
+
+/// Offset of the burn body in a CCTP v2 message, after the 148-byte header
+const CCTP_BODY: usize = 148;
+/// Offset of the hook data in a CCTP v2 message, after the 228-byte burn body
+const CCTP_HOOK_DATA: usize = CCTP_BODY + 228;
+
+/// The parts of a CCTP v2 burn message the receiver checks
+struct CctpBurn<'a> {
+    source_domain: u32,
+    // Contract the message is delivered to, the token messenger for burns
+    recipient: B256,
+    mint_recipient: B256,
+    amount: U256,
+    // Account that called `depositForBurnWithHook` on the source domain
+    depositor: B256,
+    fee_executed: U256,
+    hook_data: &'a [u8],
+}
+
+impl<'a> CctpBurn<'a> {
+    /// Read the header and burn body fields, or `None` if `message` is too short
+    fn parse(message: &'a [u8]) -> Option<Self> {
+        let hook_data = message.get(CCTP_HOOK_DATA..)?;
+        let word = |at: usize| B256::from_slice(&message[at..at + 32]);
+        Some(Self {
+            source_domain: u32::from_be_bytes(message[4..8].try_into().ok()?),
+            recipient: word(76),
+            mint_recipient: word(CCTP_BODY + 36),
+            amount: U256::from_be_bytes(word(CCTP_BODY + 68).0),
+            depositor: word(CCTP_BODY + 100),
+            fee_executed: U256::from_be_bytes(word(CCTP_BODY + 164).0),
+            hook_data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::settlement::encode_message;
+    use alloy_sol_types::{SolCall, SolEvent};
+    use stylus_sdk::testing::*;
+
+    const OWNER: Address = Address::repeat_byte(0xAA);
+    const STARGATE: Address = Address::repeat_byte(0x51);
+    const ACROSS: Address = Address::repeat_byte(0x52);
+    const CCTP: Address = Address::repeat_byte(0x53);
+    const MESSENGER: Address = Address::repeat_byte(0x54);
+    const TOKEN: Address = Address::repeat_byte(0x30);
+    const USDC: Address = Address::repeat_byte(0x31);
+    const RECIPIENT: Address = Address::repeat_byte(0x20);
+    // Source router's adapter on Stargate chain 110 and CCTP domain 3
+    const SOURCE: Address = Address::repeat_byte(0x70);
+    const SOURCE_CHAIN: u16 = 110;
+    const SOURCE_DOMAIN: u32 = 3;
+
+    fn setup() -> (TestVM, SettlementReceiver) {
+        let vm = TestVM::default();
+        vm.set_sender(OWNER);
+        let mut receiver = SettlementReceiver::from(&vm);
+        receiver.initialize(STARGATE, ACROSS, CCTP, MESSENGER, USDC).unwrap();
+        vm.set_code(TOKEN, vec![0xfe]);
+        vm.set_code(USDC, vec![0xfe]);
+        let source = SOURCE.into_word();
+        receiver
+            .set_trusted_source(BRIDGE_STARGATE, u32::from(SOURCE_CHAIN), source, true)
+            .unwrap();
+        receiver.set_trusted_source(BRIDGE_CCTP, SOURCE_DOMAIN, source, true).unwrap();
+        (vm, receiver)
+    }
+
+    /// A CCTP v2 burn of `amount` by `depositor` on `SOURCE_DOMAIN`, minted to the
+    /// receiver less `fee`, carrying `hook_data`
+    fn burn_message(
+        vm: &TestVM,
+        depositor: Address,
+        amount: U256,
+        fee: U256,
+        hook_data: &[u8],
+    ) -> Vec<u8> {
+        let mut message = vec![0; CCTP_BODY + 4];
+        message[4..8].copy_from_slice(&SOURCE_DOMAIN.to_be_bytes());
+        message[76..108].copy_from_slice(MESSENGER.into_word().as_slice());
+        for word in [
+            USDC.into_word(),
+            vm.contract_address().into_word(),
+            amount.into(),
+            depositor.into_word(),
+            fee.into(),
+            fee.into(),
+            B256::ZERO,
+        ] {
+            message.extend_from_slice(word.as_slice());
+        }
+        message.extend_from_slice(hook_data);
+        message
+    }
+
+    /// Have the message transmitter accept `message`
+    fn mock_relay(vm: &TestVM, message: &[u8]) {
+        let call = IMessageTransmitter::receiveMessageCall {
+            message: message.to_vec().into(),
+            attestation: vec![0xa7].into(),
+        };
+        vm.mock_call(CCTP, call.abi_encode(), Ok(true.abi_encode()));
+    }
+
+    #[test]
+    fn test_bridge_callbacks_settle_once() {
+        let (vm, mut receiver) = setup();
+        let amount = U256::from(1_000_000);
+        let stargate_id = B256::repeat_byte(0x01);
+        let across_id = B256::repeat_byte(0x02);
+        let cctp_id = B256::repeat_byte(0x03);
+        vm.set_block_timestamp(1_000);
+
+        vm.set_sender(STARGATE);
+        let source = Bytes(SOURCE.to_vec());
+        let payload = Bytes(encode_message(stargate_id, RECIPIENT, amount, &[]));
+        receiver
+            .sg_receive(SOURCE_CHAIN, source.clone(), U256::ZERO, TOKEN, amount, payload.clone())
+            .unwrap();
+        assert_eq!(receiver.is_settled(stargate_id), (true, true));
+        assert_eq!(
+            receiver.get_settlement(stargate_id),
+            (RECIPIENT, TOKEN, amount, U256::from(1_000))
+        );
+        assert!(matches!(
+            receiver.sg_receive(SOURCE_CHAIN, source, U256::ZERO, TOKEN, amount, payload),
+            Err(SettlementError::TransferAlreadySettled(_))
+        ));
+
+        vm.set_sender(ACROSS);
+        let message = Bytes(encode_message(across_id, RECIPIENT, amount, &[]));
+        receiver
+            .handle_v3_across_message(TOKEN, amount, Address::repeat_byte(0x60), message)
+            .unwrap();
+        assert_eq!(receiver.is_settled(across_id), (true, false));
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        assert!(!TransferSettled::decode_raw_log(topics, &data, true).unwrap().verified);
+
+        // Anyone relays a CCTP burn, crediting what it minted net of the fee...
+        vm.set_sender(Address::repeat_byte(0x61));
+        let fee = U256::from(100);
+        let body = encode_message(cctp_id, RECIPIENT, amount, &[]);
+        let message = burn_message(&vm, SOURCE, amount, fee, &body);
+        mock_relay(&vm, &message);
+        receiver.relay_cctp_burn(Bytes(message), Bytes(vec![0xa7])).unwrap();
+        assert_eq!(receiver.get_cctp_credit(cctp_id), amount - fee);
+        assert!(matches!(
+            receiver.relay_cctp_burn(Bytes(vec![0x01]), Bytes(vec![0xa7])),
+            Err(SettlementError::InvalidSettlementMessage(_))
+        ));
+
+        // ...which the message then settles
+        vm.set_sender(CCTP);
+        let sender = SOURCE.into_word();
+        assert!(receiver.handle_receive_message(SOURCE_DOMAIN, sender, Bytes(body)).unwrap());
+        assert_eq!(
+            receiver.get_settlement(cctp_id),
+            (RECIPIENT, USDC, amount - fee, U256::from(1_000))
+        );
+        assert_eq!(receiver.get_cctp_credit(cctp_id), U256::ZERO);
+
+        assert_eq!(receiver.get_total_settled(), U256::from(3));
+        let (topics, data) = vm.get_emitted_logs().pop().unwrap();
+        let event = TransferSettled::decode_raw_log(topics, &data, true).unwrap();
+        assert_eq!(event.transferId, cctp_id);
+        assert_eq!(event.bridge, BRIDGE_CCTP);
+        assert!(event.verified);
+    }
+
+    #[test]
+    fn test_untrusted_sources_cannot_settle() {
+        let (vm, mut receiver) = setup();
+        let amount = U256::from(1_000_000);
+        let transfer_id = B256::repeat_byte(0x01);
+        let payload = Bytes(encode_message(transfer_id, RECIPIENT, amount, &[]));
+        let stranger = Address::repeat_byte(0x66);
+
+        // Stargate needs a trusted sender on a trusted chain
+        vm.set_sender(STARGATE);
+        let source = Bytes(SOURCE.to_vec());
+        assert!(matches!(
+            receiver.sg_receive(
+                SOURCE_CHAIN,
+                Bytes(stranger.to_vec()),
+                U256::ZERO,
+                TOKEN,
+                amount,
+                payload.clone(),
+            ),
+            Err(SettlementError::UntrustedSource(_))
+        ));
+        assert!(matches!(
+            receiver.sg_receive(111, source.clone(), U256::ZERO, TOKEN, amount, payload.clone()),
+            Err(SettlementError::UntrustedSource(_))
+        ));
+
+        // CCTP needs a trusted sender and a burn by a trusted depositor that the
+        // transmitter mints here
+        vm.set_sender(CCTP);
+        assert!(matches!(
+            receiver.handle_receive_message(SOURCE_DOMAIN, stranger.into_word(), payload.clone()),
+            Err(SettlementError::UntrustedSource(_))
+        ));
+        assert!(matches!(
+            receiver.handle_receive_message(SOURCE_DOMAIN, SOURCE.into_word(), payload.clone()),
+            Err(SettlementError::SettlementNotFunded(_))
+        ));
+        let attestation = Bytes(vec![0xa7]);
+        let burn = |depositor: Address| burn_message(&vm, depositor, amount, U256::ZERO, &payload);
+        assert!(matches!(
+            receiver.relay_cctp_burn(Bytes(burn(stranger)), attestation.clone()),
+            Err(SettlementError::UntrustedSource(_))
+        ));
+        let mut minted_elsewhere = burn(SOURCE);
+        minted_elsewhere[CCTP_BODY + 36..CCTP_BODY + 68]
+            .copy_from_slice(stranger.into_word().as_slice());
+        assert!(matches!(
+            receiver.relay_cctp_burn(Bytes(minted_elsewhere), attestation.clone()),
+            Err(SettlementError::InvalidSettlementMessage(_))
+        ));
+        assert!(matches!(
+            receiver.relay_cctp_burn(Bytes(burn(SOURCE)), attestation),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+
+        // Anyone can deposit an Across message naming the transfer, but dust
+        // does not block the real delivery
+        vm.set_sender(ACROSS);
+        let dust = Bytes(encode_message(transfer_id, stranger, U256::from(1), &[]));
+        receiver.handle_v3_across_message(TOKEN, U256::from(1), stranger, dust).unwrap();
+        vm.set_sender(STARGATE);
+        receiver
+            .sg_receive(SOURCE_CHAIN, source, U256::ZERO, TOKEN, amount, payload.clone())
+            .unwrap();
+        assert_eq!(receiver.get_settlement(transfer_id).0, RECIPIENT);
+        assert_eq!(receiver.get_settlement(transfer_id).2, amount);
+        assert_eq!(receiver.get_total_settled(), U256::from(1));
+
+        // ...and the verified settlement is final
+        vm.set_sender(ACROSS);
+        assert!(matches!(
+            receiver.handle_v3_across_message(TOKEN, amount, stranger, payload),
+            Err(SettlementError::TransferAlreadySettled(_))
+        ));
+
+        vm.set_sender(stranger);
+        assert!(matches!(
+            receiver.set_trusted_source(BRIDGE_CCTP, SOURCE_DOMAIN, stranger.into_word(), true),
+            Err(SettlementError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        receiver.set_trusted_source(BRIDGE_CCTP, SOURCE_DOMAIN, SOURCE.into_word(), false).unwrap();
+        assert!(!receiver.is_trusted_source(BRIDGE_CCTP, SOURCE_DOMAIN, SOURCE.into_word()));
+    }
+
+    #[test]
+    fn test_only_endpoints_deliver_settlements() {
+        let (vm, mut receiver) = setup();
+        let transfer_id = B256::repeat_byte(0x01);
+        let message = Bytes(encode_message(transfer_id, RECIPIENT, U256::from(1), &[]));
+
+        // The Stargate router cannot impersonate the Across spoke pool
+        vm.set_sender(STARGATE);
+        assert!(matches!(
+            receiver.handle_v3_across_message(TOKEN, U256::from(1), Address::ZERO, message.clone()),
+            Err(SettlementError::UnauthorizedCaller(_))
+        ));
+
+        // A failed forward reverts the settlement
+        vm.mock_call(
+            TOKEN,
+            IERC20::transferCall {
+                to: RECIPIENT,
+                amount: U256::from(1),
+            }
+            .abi_encode(),
+            Err(vec![]),
+        );
+        let source = Bytes(SOURCE.to_vec());
+        assert!(receiver
+            .sg_receive(SOURCE_CHAIN, source.clone(), U256::ZERO, TOKEN, U256::from(1), message)
+            .is_err());
+
+        // So does a recipient rejecting the payload
+        vm.set_sender(ACROSS);
+        let other_id = B256::repeat_byte(0x02);
+        let with_payload = encode_message(other_id, RECIPIENT, U256::from(2), &[0x01]);
+        let on_settlement = ISettlementRecipient::onSettlementCall {
+            transferId: other_id,
+            token: TOKEN,
+            amount: U256::from(2),
+            payload: vec![0x01].into(),
+        };
+        vm.mock_call(RECIPIENT, on_settlement.abi_encode(), Err(vec![]));
+        assert!(matches!(
+            receiver.handle_v3_across_message(TOKEN, U256::from(2), RECIPIENT, Bytes(with_payload)),
+            Err(SettlementError::ExternalCallFailed(_))
+        ));
+        vm.set_sender(STARGATE);
+
+        assert!(matches!(
+            receiver.set_endpoints(Address::ZERO, ACROSS, CCTP, MESSENGER, USDC),
+            Err(SettlementError::NotOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        receiver.set_endpoints(Address::ZERO, ACROSS, CCTP, MESSENGER, USDC).unwrap();
+        assert_eq!(receiver.get_endpoints(), (Address::ZERO, ACROSS, CCTP, MESSENGER, USDC));
+    }
+}
@@ -0,0 +1,49 @@
+//! Storage-backed reentrancy lock shared by the contracts in the crate.
+//!
+//! The SDK already rejects reentrant calls unless it is built with its
+//! `reentrant` feature; the lock keeps mutating methods safe either way. A
+//! contract embeds `ReentrancyGuard`, implements `NonReentrant` and wraps each
+//! state-mutating public method in `non_reentrant`.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::prelude::*;
+
+use crate::errors::{ReentrantCall, SettlementError};
+
+sol_storage! {
+    pub struct ReentrancyGuard {
+        bool locked;
+    }
+}
+
+impl ReentrancyGuard {
+    /// Take the lock. Fails if a call further up the stack holds it.
+    pub fn enter(&mut self) -> Result<(), SettlementError> {
+        if self.locked.get() {
+            return Err(ReentrantCall {}.into());
+        }
+        self.locked.set(true);
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.locked.set(false);
+    }
+}
+
+/// Implemented by contracts that embed a `ReentrancyGuard`
+pub trait NonReentrant: Sized {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard;
+
+    /// Run `f` holding the lock. The lock is released when `f` fails too, so it
+    /// never outlives the call.
+    fn non_reentrant<T, E: From<SettlementError>>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.reentrancy_guard().enter()?;
+        let result = f(self);
+        self.reentrancy_guard().exit();
+        result
+    }
+}
@@ -1,17 +1,57 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::SolValue;
 use stylus_sdk::{
     alloc::{string::String, vec, vec::Vec},
+    block,
+    contract,
+    crypto::keccak,
+    evm,
     msg,
     prelude::*,
     storage::{StorageMap, StorageVec},
 };
 
 use crate::adapters::BridgeRoute;
+use crate::{
+    NATIVE_TOKEN, OP_ADD_BRIDGE_ADAPTER, OP_RENOUNCE_OWNERSHIP, OP_SET_FEE_RECIPIENT,
+    OP_SET_MAX_INTEGRATOR_FEE, OP_SET_PRICE_ORACLE, OP_SET_PROTOCOL_FEE, OP_SET_TIMELOCK_DELAY,
+    OP_SET_SCREENING_ORACLE, OP_TRANSFER_OWNERSHIP, TRANSFER_COMPLETED, TRANSFER_FAILED, TRANSFER_PENDING,
+};
+use crate::erc20::{ERC20Helper, IERC20};
+use crate::errors::{
+    AdapterExists, AdapterNotFound, AdapterPaused, AddressBlocked, BridgeNotSupported,
+    ChainPaused, DeadlineExpired, ExposureCapExceeded, FeeTooHigh, InsufficientOutput,
+    InsufficientRefundBalance, InvalidAdapter, InvalidAddress, InvalidAmount, InvalidMaxRoutes,
+    InvalidOperation, InvalidOracle, InvalidSpeedWeight, InvalidSplitConfig, InvalidTimelockDelay,
+    InvalidToken, InvalidTransferStatus, NativeDropNotSupported, NoRefundAvailable,
+    NoRouteAvailable, NotTransferSender, OperationAlreadyQueued, OperationNotQueued,
+    OperationNotReady, OracleNotSet, PayloadNotSupported, RateLimitExceeded, RefundTokenMismatch,
+    RoutingPaused, ScreeningFailed, SettlementError, TimelockRequired, TokenNotSupported, TokenPaused, TransferLimitExceeded, TransferNotFound,
+    UserRateLimitExceeded,
+};
+use crate::events::{
+    AdapterExposureCapUpdated, AdapterPauseUpdated, BatchLegRefunded, BridgeAdapterAdded,
+    BridgeAdapterRemoved, ChainPauseUpdated, CrossChainTransferInitiated, DenylistUpdated,
+    FeeRecipientUpdated, FeesWithdrawn, IntegratorFeesClaimed, MaxIntegratorFeeUpdated,
+    MaxRoutesUpdated, OperationCancelled, OperationExecuted, OperationQueued, Paused,
+    PriceOracleUpdated, ProtocolFeeUpdated, RateLimitsUpdated, RefundClaimed, RefundProcessed,
+    ScreeningOracleUpdated, SplitConfigUpdated, TimelockDelayUpdated, TokenPauseUpdated,
+    TokenSupportUpdated, TransferStatusUpdated, Unpaused, ValueOfTimeUpdated,
+};
+use crate::access_control::{
+    AccessControl, ADAPTER_MANAGER_ROLE, FEE_MANAGER_ROLE, PAUSER_ROLE, RELAYER_ROLE,
+};
+use crate::ownable::Ownable;
+use crate::rate_limit::{RateBucket, DAY, HOUR};
+use crate::reentrancy_guard::{NonReentrant, ReentrancyGuard};
 
 // External interfaces for adapters and oracle (called by address)
 sol_interface! {
     interface IBridgeAdapterExternal {
         function get_bridge_info() external view returns (string, bool);
+        function supports_native() external view returns (bool);
+        function supports_native_drop() external view returns (bool);
+        function supports_payload() external view returns (bool);
         function get_route(
             uint256 from_chain,
             uint256 to_chain,
@@ -25,12 +65,16 @@ sol_interface! {
             bool available
         );
         function bridge_tokens(
+            bytes32 transfer_id,
             uint256 to_chain,
             address token,
             uint256 amount,
             address recipient,
+            uint256 min_amount_out,
+            uint256 deadline,
+            uint256 native_drop_amount,
             bytes data
-        ) external;
+        ) external payable;
     }
 }
 
@@ -43,6 +87,13 @@ sol_interface! {
     }
 }
 
+// Chainalysis sanctions oracle
+sol_interface! {
+    interface ISanctionsList {
+        function isSanctioned(address addr) external view returns (bool);
+    }
+}
+
 #[derive(Debug)]
 pub struct RouteInfo {
     pub bridge_adapter: Address,
@@ -50,6 +101,12 @@ pub struct RouteInfo {
     pub estimated_time: U256,
     pub estimated_gas_cost: U256, // In USD (8 decimals)
     pub bridge_fee: U256,         // In token amount
+    pub bridge_fee_usd: U256,     // Bridge fee in USD (8 decimals)
+    pub protocol_fee: U256,       // In token amount, taken before bridging
+    pub protocol_fee_usd: U256,   // Protocol fee in USD (8 decimals)
+    pub integrator_fee: U256,     // In token amount, taken before bridging
+    pub integrator_fee_usd: U256, // Integrator fee in USD (8 decimals)
+    pub native_drop_usd: U256,    // Destination gas drop-off in USD (8 decimals)
     pub total_cost_usd: U256,     // Total cost in USD (8 decimals)
     pub amount_out: U256,         // Amount after fees
     pub available: bool,
@@ -62,8 +119,23 @@ pub struct TransferRequest {
     pub token: Address,
     pub amount: U256,
     pub recipient: Address,
+    pub speed_weight_bps: U256, // 0 = cheapest, 10_000 = fastest
+    pub min_amount_out: U256,   // Revert if the recipient would get less
+    pub deadline: U256,         // Unix timestamp after which the transfer reverts
+    pub native_drop_amount: U256, // Destination native gas for the recipient (wei)
+    pub payload: Vec<u8>,         // Delivered with the funds as a call to `recipient`
+    pub integrator: Address,      // Partner wallet taking a cut; zero for none
+    pub integrator_fee_bps: U256, // Integrator cut, up to `max_integrator_fee_bps`
 }
 
+const MAX_SPEED_WEIGHT_BPS: u64 = 10_000;
+const DEFAULT_VALUE_OF_TIME: u64 = 10_000; // $0.0001 per second (8 decimals)
+const MAX_PROTOCOL_FEE_BPS: u64 = 100; // 1%
+const MAX_INTEGRATOR_FEE_BPS: u64 = 300; // Ceiling for the owner-set maximum: 3%
+const MAX_TIMELOCK_DELAY: u64 = 30 * 24 * 60 * 60; // 30 days
+const FAILURE_PENALTY_BPS: u64 = 1_000; // Score +10% per recent adapter failure
+const MAX_PENALIZED_FAILURES: u64 = 5;
+
 #[storage]
 pub struct StablecoinRouter {
     // Price oracle for gas and token prices
@@ -79,75 +151,596 @@ pub struct StablecoinRouter {
 
     // Route finding parameters
     max_routes: U256,
+    max_split_legs: U256,      // Adapters a split transfer may use
+    split_increment_bps: U256, // Allocation step for split quotes
+    value_of_time: U256, // USD per second of transfer time (8 decimals)
 
     // Statistics
     total_transfers: U256,
     total_volume_usd: U256,
 
-    // Owner
-    owner: Address,
+    // Owner and pending owner
+    #[borrow]
+    ownable: Ownable,
+
+    // Dispatched transfers by id, and refunds bridges pushed back for them
+    transfer_senders: StorageMap<B256, Address>,
+    refund_tokens: StorageMap<B256, Address>,
+    refund_amounts: StorageMap<B256, U256>,
+    refund_liabilities: StorageMap<Address, U256>, // Unclaimed refunds per token
+
+    // Protocol fee on the input amount, accrued per token until withdrawn
+    protocol_fee_bps: U256,
+    fee_recipient: Address,
+    accrued_fees: StorageMap<Address, U256>,
+
+    // Integrator fees by (integrator, token), their total per token, and per-integrator stats
+    max_integrator_fee_bps: U256,
+    integrator_fees: StorageMap<(Address, Address), U256>,
+    integrator_fee_liabilities: StorageMap<Address, U256>,
+    integrator_transfers: StorageMap<Address, U256>,
+    integrator_volume_usd: StorageMap<Address, U256>,
+
+    // Role members; the deployer starts as admin
+    #[borrow]
+    access_control: AccessControl,
+
+    // Emergency stops: pausers set them, only the owner clears them
+    paused: bool,
+    paused_adapters: StorageMap<Address, bool>,
+    paused_chains: StorageMap<U256, bool>,
+    paused_tokens: StorageMap<Address, bool>,
+
+    // Timelock: zero delay applies sensitive changes directly; otherwise they are
+    // queued by operation id and executable from the stored eta
+    timelock_delay: U256,
+    operation_etas: StorageMap<B256, U256>,
+
+    // Held for the duration of every state-mutating call
+    reentrancy_guard: ReentrancyGuard,
+
+    // Rate limits per token (zero = unlimited): largest single transfer, rolling
+    // hourly and daily volume, and rolling daily volume per (token, sender)
+    max_transfer_amounts: StorageMap<Address, U256>,
+    hourly_limits: StorageMap<Address, U256>,
+    daily_limits: StorageMap<Address, U256>,
+    user_daily_limits: StorageMap<Address, U256>,
+    hourly_usage: StorageMap<Address, RateBucket>,
+    daily_usage: StorageMap<Address, RateBucket>,
+    user_daily_usage: StorageMap<(Address, Address), RateBucket>,
+
+    // Adapter risk: owner-set cap on in-flight USD volume (zero = none), volume in
+    // flight, and outcomes from relayer status reports with the current failure run
+    adapter_exposure_caps: StorageMap<Address, U256>,
+    adapter_exposure: StorageMap<Address, U256>,
+    adapter_successes: StorageMap<Address, U256>,
+    adapter_failures: StorageMap<Address, U256>,
+    adapter_recent_failures: StorageMap<Address, U256>,
+    adapter_last_failure: StorageMap<Address, U256>,
+
+    // Dispatched transfers by id: adapter, USD volume in flight and status
+    transfer_adapters: StorageMap<B256, Address>,
+    transfer_volumes_usd: StorageMap<B256, U256>,
+    transfer_statuses: StorageMap<B256, u8>,
+
+    // Screening: optional sanctions oracle and owner-managed denylist, checked for
+    // the sender and recipient of every quote and transfer
+    screening_oracle: Address,
+    denylist: StorageMap<Address, bool>,
+}
+
+impl NonReentrant for StablecoinRouter {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.reentrancy_guard
+    }
 }
 
 impl StablecoinRouter {
-    pub fn new(price_oracle: Address) -> Result<Self, Vec<u8>> {
-        ensure!(price_oracle != Address::ZERO, "Invalid oracle address");
+    pub fn new(price_oracle: Address) -> Result<Self, SettlementError> {
+        ensure!(price_oracle != Address::ZERO, InvalidOracle {});
 
         let mut instance = Self::default();
         instance.price_oracle = price_oracle;
-        instance.owner = msg::sender();
+        instance.ownable.init(msg::sender())?;
+        instance.access_control.init(msg::sender());
         instance.max_routes = U256::from(5);
-        
+        instance.max_split_legs = U256::from(3);
+        instance.split_increment_bps = U256::from(2_500);
+        instance.value_of_time = U256::from(DEFAULT_VALUE_OF_TIME);
+
+        evm::log(PriceOracleUpdated { previousOracle: Address::ZERO, newOracle: price_oracle });
+
         Ok(instance)
     }
 
-    pub fn add_bridge_adapter(&mut self, adapter: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(adapter != Address::ZERO, "Invalid adapter address");
-        ensure!(!self.is_bridge_adapter.get(&adapter).unwrap_or(&false), "Adapter exists");
+    pub fn set_price_oracle(&mut self, price_oracle: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_price_oracle(price_oracle)
+        })
+    }
+
+    pub fn add_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADAPTER_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_bridge_adapter(adapter)
+        })
+    }
 
-        // Verify the adapter implements IBridgeAdapter
-        let bridge = self.get_bridge_adapter(adapter)?;
-        let (name, supported) = bridge.get_bridge_info()?;
-        ensure!(supported, "Bridge not supported");
+    pub fn remove_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(ADAPTER_MANAGER_ROLE)?;
+            ensure!(this.is_bridge_adapter.get(&adapter).unwrap_or(&false), AdapterNotFound { adapter });
 
-        self.bridge_adapters.push(adapter);
-        self.is_bridge_adapter.insert(adapter, true);
+            // Remove from is_bridge_adapter mapping
+            this.is_bridge_adapter.insert(adapter, false);
 
-        // Emit event would go here
-        Ok(())
+            // Remove from bridge_adapters vec (this is inefficient, but matches Solidity behavior)
+            let mut new_adapters = Vec::new();
+            for i in 0..this.bridge_adapters.len() {
+                let current = this.bridge_adapters.get(i).unwrap();
+                if current != adapter {
+                    new_adapters.push(current);
+                }
+            }
+
+            // Clear and repopulate bridge_adapters
+            while this.bridge_adapters.pop().is_some() {}
+            for adapter in new_adapters {
+                this.bridge_adapters.push(adapter);
+            }
+
+            evm::log(BridgeAdapterRemoved { adapter });
+            Ok(())
+        })
     }
 
-    pub fn remove_bridge_adapter(&mut self, adapter: Address) -> Result<(), Vec<u8>> {
-        self.ensure_owner()?;
-        ensure!(self.is_bridge_adapter.get(&adapter).unwrap_or(&false), "Adapter not found");
+    pub fn set_token_support(&mut self, token: Address, supported: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
+            if *this.supported_tokens.get(&token).unwrap_or(&false) == supported {
+                return Ok(());
+            }
 
-        // Remove from is_bridge_adapter mapping
-        self.is_bridge_adapter.insert(adapter, false);
+            this.supported_tokens.insert(token, supported);
+            if supported {
+                this.token_list.push(token);
+            } else {
+                let mut remaining = Vec::new();
+                for i in 0..this.token_list.len() {
+                    let current = this.token_list.get(i).unwrap();
+                    if current != token {
+                        remaining.push(current);
+                    }
+                }
 
-        // Remove from bridge_adapters vec (this is inefficient, but matches Solidity behavior)
-        let mut new_adapters = Vec::new();
-        for i in 0..self.bridge_adapters.len() {
-            let current = self.bridge_adapters.get(i).unwrap();
-            if current != adapter {
-                new_adapters.push(current);
+                while this.token_list.pop().is_some() {}
+                for token in remaining {
+                    this.token_list.push(token);
+                }
             }
-        }
 
-        // Clear and repopulate bridge_adapters
-        while self.bridge_adapters.pop().is_some() {}
-        for adapter in new_adapters {
-            self.bridge_adapters.push(adapter);
-        }
+            evm::log(TokenSupportUpdated { token, supported });
+            Ok(())
+        })
+    }
 
-        // Emit event would go here
-        Ok(())
+    pub fn set_value_of_time(&mut self, value_per_second: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+
+            let previous = this.value_of_time;
+            this.value_of_time = value_per_second;
+
+            evm::log(ValueOfTimeUpdated { previousValue: previous, newValue: value_per_second });
+            Ok(())
+        })
+    }
+
+    pub fn set_max_routes(&mut self, max_routes: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(max_routes != U256::ZERO, InvalidMaxRoutes {});
+
+            let previous = this.max_routes;
+            this.max_routes = max_routes;
+
+            evm::log(MaxRoutesUpdated { previousValue: previous, newValue: max_routes });
+            Ok(())
+        })
+    }
+
+    pub fn set_split_config(&mut self, max_legs: U256, increment_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            let max_bps = U256::from(10_000);
+            ensure!(
+                max_legs != U256::ZERO
+                    && increment_bps != U256::ZERO
+                    && increment_bps <= max_bps
+                    && max_bps % increment_bps == U256::ZERO,
+                InvalidSplitConfig { maxLegs: max_legs, incrementBps: increment_bps }
+            );
+
+            this.max_split_legs = max_legs;
+            this.split_increment_bps = increment_bps;
+
+            evm::log(SplitConfigUpdated { maxLegs: max_legs, incrementBps: increment_bps });
+            Ok(())
+        })
+    }
+
+    /// Protocol fee taken from every transfer's input amount, at most 1%
+    pub fn set_protocol_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_protocol_fee(fee_bps)
+        })
+    }
+
+    pub fn set_fee_recipient(&mut self, recipient: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_fee_recipient(recipient)
+        })
+    }
+
+    /// Send the protocol fees accrued in `token` to the fee recipient. Fee
+    /// managers can withdraw, but only the owner can change the recipient.
+    pub fn withdraw_fees(&mut self, token: Address) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            let recipient = this.fee_recipient;
+            ensure!(recipient != Address::ZERO, InvalidAddress {});
+            let amount = *this.accrued_fees.get(&token).unwrap_or(&U256::ZERO);
+            ensure!(amount > U256::ZERO, InvalidAmount {});
+
+            this.accrued_fees.insert(token, U256::ZERO);
+            ERC20Helper::transfer(token, recipient, amount)?;
+
+            evm::log(FeesWithdrawn { token, recipient, amount });
+            Ok(amount)
+        })
+    }
+
+    /// Highest `integrator_fee_bps` a transfer may carry, at most 3%
+    pub fn set_max_integrator_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(FEE_MANAGER_ROLE)?;
+            this.ensure_timelock_off()?;
+            this.apply_max_integrator_fee(fee_bps)
+        })
+    }
+
+    /// Claim the caller's integrator fees in `token`
+    pub fn claim_integrator_fees(&mut self, token: Address) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            let integrator = msg::sender();
+            let amount = *this.integrator_fees.get(&(integrator, token)).unwrap_or(&U256::ZERO);
+            ensure!(amount > U256::ZERO, InvalidAmount {});
+
+            this.integrator_fees.insert((integrator, token), U256::ZERO);
+            let liabilities = *this.integrator_fee_liabilities.get(&token).unwrap_or(&U256::ZERO);
+            this.integrator_fee_liabilities.insert(token, liabilities - amount);
+            ERC20Helper::transfer(token, integrator, amount)?;
+
+            evm::log(IntegratorFeesClaimed { integrator, token, amount });
+            Ok(amount)
+        })
+    }
+
+    /// Per-integrator stats: (transfers, volume_usd)
+    pub fn get_integrator_stats(&self, integrator: Address) -> (U256, U256) {
+        (
+            *self.integrator_transfers.get(&integrator).unwrap_or(&U256::ZERO),
+            *self.integrator_volume_usd.get(&integrator).unwrap_or(&U256::ZERO),
+        )
     }
 
-    pub fn find_best_route(&self, request: TransferRequest) -> Result<RouteInfo, Vec<u8>> {
-        ensure!(self.supported_tokens.get(&request.token).unwrap_or(&false), "Token not supported");
+    /// Stop all routing. Pausers can pause; only the owner can unpause.
+    pub fn pause(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(PAUSER_ROLE)?;
+            this.paused = true;
+            evm::log(Paused { account: msg::sender() });
+            Ok(())
+        })
+    }
+
+    pub fn unpause(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.paused = false;
+            evm::log(Unpaused { account: msg::sender() });
+            Ok(())
+        })
+    }
+
+    pub fn set_adapter_paused(&mut self, adapter: Address, paused: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_adapters.insert(adapter, paused);
+            evm::log(AdapterPauseUpdated { adapter, paused, account: msg::sender() });
+            Ok(())
+        })
+    }
+
+    pub fn set_chain_paused(&mut self, chain_id: U256, paused: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_chains.insert(chain_id, paused);
+            evm::log(ChainPauseUpdated { chainId: chain_id, paused, account: msg::sender() });
+            Ok(())
+        })
+    }
+
+    pub fn set_token_paused(&mut self, token: Address, paused: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_pause_change(paused)?;
+            this.paused_tokens.insert(token, paused);
+            evm::log(TokenPauseUpdated { token, paused, account: msg::sender() });
+            Ok(())
+        })
+    }
+
+    /// Propose a new owner (timelocked)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.ownable.transfer_ownership(new_owner)
+        })
+    }
+
+    pub fn renounce_ownership(&mut self) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.ownable.renounce_ownership()
+        })
+    }
+
+    /// Turning the timelock on is instant; once on, changing the delay is queued
+    pub fn set_timelock_delay(&mut self, delay: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_timelock_delay(delay)
+        })
+    }
+
+    /// Queue a change of `kind` (an `OP_*` constant) to `value`. While the
+    /// timelock is on, adapter additions, oracle, fee and ownership changes
+    /// must go through the queue; pausing and removing adapters stay instant.
+    pub fn queue_operation(&mut self, kind: u8, value: U256) -> Result<B256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_operation_auth(kind)?;
+            let operation_id = keccak((U256::from(kind), value).abi_encode());
+            ensure!(
+                this.operation_etas.get(&operation_id).is_none(),
+                OperationAlreadyQueued { operationId: operation_id }
+            );
+
+            let eta = U256::from(block::timestamp()) + this.timelock_delay;
+            this.operation_etas.insert(operation_id, eta);
+
+            evm::log(OperationQueued { operationId: operation_id, kind, value, eta });
+            Ok(operation_id)
+        })
+    }
+
+    pub fn execute_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_operation_auth(kind)?;
+            let operation_id = keccak((U256::from(kind), value).abi_encode());
+            let eta = *this
+                .operation_etas
+                .get(&operation_id)
+                .ok_or_else(|| SettlementError::from(OperationNotQueued { operationId: operation_id }))?;
+            ensure!(
+                U256::from(block::timestamp()) >= eta,
+                OperationNotReady { operationId: operation_id, eta }
+            );
+
+            this.operation_etas.remove(&operation_id);
+            this.apply_operation(kind, value)?;
+
+            evm::log(OperationExecuted { operationId: operation_id, kind, value });
+            Ok(())
+        })
+    }
+
+    /// Pausers can cancel any queued operation
+    pub fn cancel_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            if this.ensure_role(PAUSER_ROLE).is_err() {
+                this.ensure_operation_auth(kind)?;
+            }
+            let operation_id = keccak((U256::from(kind), value).abi_encode());
+            ensure!(
+                this.operation_etas.remove(&operation_id).is_some(),
+                OperationNotQueued { operationId: operation_id }
+            );
+
+            evm::log(OperationCancelled { operationId: operation_id, kind, value });
+            Ok(())
+        })
+    }
+
+    /// Set `token`'s rate limits; zero lifts a limit
+    pub fn set_rate_limits(
+        &mut self,
+        token: Address,
+        max_transfer: U256,
+        hourly_limit: U256,
+        daily_limit: U256,
+        user_daily_limit: U256,
+    ) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(token != Address::ZERO, InvalidToken {});
+
+            this.max_transfer_amounts.insert(token, max_transfer);
+            this.hourly_limits.insert(token, hourly_limit);
+            this.daily_limits.insert(token, daily_limit);
+            this.user_daily_limits.insert(token, user_daily_limit);
+
+            evm::log(RateLimitsUpdated {
+                token,
+                maxTransfer: max_transfer,
+                hourlyLimit: hourly_limit,
+                dailyLimit: daily_limit,
+                userDailyLimit: user_daily_limit,
+            });
+            Ok(())
+        })
+    }
+
+    /// What each rate limit still allows `user` to send now:
+    /// (max_transfer, hourly, daily, user_daily), `U256::MAX` where unlimited
+    pub fn get_rate_limit_capacity(&self, token: Address, user: Address) -> (U256, U256, U256, U256) {
+        let now = U256::from(block::timestamp());
+        let limit = |limits: &StorageMap<Address, U256>| *limits.get(&token).unwrap_or(&U256::ZERO);
+        let max_transfer = match limit(&self.max_transfer_amounts) {
+            limit if limit == U256::ZERO => U256::MAX,
+            limit => limit,
+        };
+        (
+            max_transfer,
+            self.hourly_usage.getter(token).remaining(limit(&self.hourly_limits), HOUR, now),
+            self.daily_usage.getter(token).remaining(limit(&self.daily_limits), DAY, now),
+            self.user_daily_usage.getter((token, user)).remaining(limit(&self.user_daily_limits), DAY, now),
+        )
+    }
+
+    /// Largest amount of `token` that `user` can send now
+    pub fn get_transfer_capacity(&self, token: Address, user: Address) -> U256 {
+        let (max_transfer, hourly, daily, user_daily) = self.get_rate_limit_capacity(token, user);
+        max_transfer.min(hourly).min(daily).min(user_daily)
+    }
+
+    /// Cap the USD volume in flight through `adapter`; zero lifts the cap
+    pub fn set_adapter_exposure_cap(&mut self, adapter: Address, cap_usd: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(adapter != Address::ZERO, InvalidAdapter { adapter });
+            this.adapter_exposure_caps.insert(adapter, cap_usd);
+            evm::log(AdapterExposureCapUpdated { adapter, capUsd: cap_usd });
+            Ok(())
+        })
+    }
+
+    /// Report how a pending transfer ended, settling its volume out of the
+    /// adapter's exposure and counting the outcome toward its health
+    pub fn report_transfer_status(&mut self, transfer_id: B256, status: u8) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_role(RELAYER_ROLE)?;
+            ensure!(
+                *this.transfer_statuses.get(&transfer_id).unwrap_or(&0) == TRANSFER_PENDING
+                    && (status == TRANSFER_COMPLETED || status == TRANSFER_FAILED),
+                InvalidTransferStatus { transferId: transfer_id, status }
+            );
+
+            let adapter = *this.transfer_adapters.get(&transfer_id).unwrap_or(&Address::ZERO);
+            let volume_usd = this.transfer_volumes_usd.remove(&transfer_id).unwrap_or(U256::ZERO);
+            let exposure = *this.adapter_exposure.get(&adapter).unwrap_or(&U256::ZERO);
+            this.adapter_exposure.insert(adapter, exposure.saturating_sub(volume_usd));
+            this.transfer_statuses.insert(transfer_id, status);
+
+            let counter = if status == TRANSFER_COMPLETED {
+                &mut this.adapter_successes
+            } else {
+                let recent = this.recent_failures(adapter);
+                this.adapter_recent_failures.insert(adapter, recent + U256::from(1));
+                this.adapter_last_failure.insert(adapter, U256::from(block::timestamp()));
+                &mut this.adapter_failures
+            };
+            let count = *counter.get(&adapter).unwrap_or(&U256::ZERO);
+            counter.insert(adapter, count + U256::from(1));
+
+            evm::log(TransferStatusUpdated { transferId: transfer_id, adapter, status });
+            Ok(())
+        })
+    }
+
+    /// (successes, failures, recent_failures) reported for `adapter`
+    pub fn get_adapter_health(&self, adapter: Address) -> (U256, U256, U256) {
+        (
+            *self.adapter_successes.get(&adapter).unwrap_or(&U256::ZERO),
+            *self.adapter_failures.get(&adapter).unwrap_or(&U256::ZERO),
+            self.recent_failures(adapter),
+        )
+    }
+
+    /// Set the sanctions oracle screening senders and recipients; zero turns it off
+    pub fn set_screening_oracle(&mut self, oracle: Address) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.ensure_timelock_off()?;
+            this.apply_screening_oracle(oracle)
+        })
+    }
+
+    pub fn set_denied(&mut self, account: Address, denied: bool) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            this.denylist.insert(account, denied);
+            evm::log(DenylistUpdated { account, denied });
+            Ok(())
+        })
+    }
+
+    pub fn get_supported_tokens(&self) -> Vec<Address> {
+        (0..self.token_list.len())
+            .map(|i| self.token_list.get(i).unwrap())
+            .collect()
+    }
+
+    pub fn find_best_route(&self, request: TransferRequest) -> Result<RouteInfo, SettlementError> {
+        let (to_chain, token) = (request.to_chain, request.token);
+        self.get_routes(request)?
+            .into_iter()
+            .next()
+            .filter(|route| route.available)
+            .ok_or_else(|| NoRouteAvailable { toChain: to_chain, token }.into())
+    }
+
+    /// Top `max_routes` routes, sorted by speed-weighted score. Paused routes are
+    /// listed but unavailable.
+    pub fn get_routes(&self, request: TransferRequest) -> Result<Vec<RouteInfo>, SettlementError> {
+        self.ensure_not_blocked(&[msg::sender(), request.recipient])?;
+        ensure!(
+            self.supported_tokens.get(&request.token).unwrap_or(&false),
+            TokenNotSupported { token: request.token }
+        );
         
-        let mut best_route: Option<RouteInfo> = None;
-        let mut best_cost = U256::MAX;
+        let max_weight = U256::from(MAX_SPEED_WEIGHT_BPS);
+        ensure!(
+            request.speed_weight_bps <= max_weight,
+            InvalidSpeedWeight { speedWeightBps: request.speed_weight_bps }
+        );
+        self.ensure_integrator(&request)?;
+
+        let oracle = self.price_oracle()?;
+        let token_price = oracle.get_token_price(request.token)?;
+        let token_unit = U256::from(10).pow(U256::from(IERC20::new(request.token).decimals()?));
+        let native_drop_usd = request
+            .native_drop_amount
+            .saturating_mul(oracle.get_native_token_price(request.to_chain)?)
+            / U256::from(10).pow(U256::from(18));
+
+        // Adapters are quoted on what is left after the protocol and integrator fees
+        let (protocol_fee, integrator_fee) = self.transfer_fees(&request, request.amount);
+        let protocol_fee_usd = protocol_fee.saturating_mul(token_price) / token_unit;
+        let integrator_fee_usd = integrator_fee.saturating_mul(token_price) / token_unit;
+        let bridged_amount = request.amount - protocol_fee - integrator_fee;
+
+        let mut routes: Vec<(U256, RouteInfo)> = Vec::new();
 
         for i in 0..self.bridge_adapters.len() {
             let adapter_addr = self.bridge_adapters.get(i).unwrap();
@@ -158,7 +751,7 @@ impl StablecoinRouter {
                 request.from_chain,
                 request.to_chain,
                 request.token,
-                request.amount,
+                bridged_amount,
             ) {
                 Ok((bridge_name, estimated_time, estimated_gas, fee, available)) => BridgeRoute {
                     bridge_name,
@@ -174,69 +767,681 @@ impl StablecoinRouter {
                 continue;
             }
 
-            // Calculate total cost
-            let gas_cost = self.price_oracle()?.calculate_gas_cost(
+            // Bridges that cannot drop destination gas or deliver the payload are
+            // listed but unavailable
+            let available = (request.native_drop_amount == U256::ZERO
+                || adapter.supports_native_drop().unwrap_or(false))
+                && (request.payload.is_empty() || adapter.supports_payload().unwrap_or(false))
+                && !self.route_paused(adapter_addr, &request)
+                && request.amount.saturating_mul(token_price) / token_unit
+                    <= self.exposure_remaining(adapter_addr);
+
+            // Total USD cost: source-chain gas, all fees and any gas drop-off
+            let gas_cost = oracle.calculate_gas_cost(request.from_chain, route.estimated_gas)?;
+            let fee_usd = route.fee.saturating_mul(token_price) / token_unit;
+            let total_cost = gas_cost
+                .saturating_add(fee_usd)
+                .saturating_add(protocol_fee_usd)
+                .saturating_add(integrator_fee_usd)
+                .saturating_add(native_drop_usd);
+
+            // Weigh cost against time the same way the UI slider does, then penalise
+            // adapters that failed recently
+            let score = total_cost
+                .saturating_mul(max_weight - request.speed_weight_bps)
+                .saturating_add(
+                    self.value_of_time
+                        .saturating_mul(route.estimated_time)
+                        .saturating_mul(request.speed_weight_bps),
+                );
+            let failures = self.recent_failures(adapter_addr).min(U256::from(MAX_PENALIZED_FAILURES));
+            let score = score.saturating_add(
+                score.saturating_mul(failures * U256::from(FAILURE_PENALTY_BPS)) / U256::from(10_000),
+            );
+
+            routes.push((score, RouteInfo {
+                bridge_adapter: adapter_addr,
+                bridge_name: route.bridge_name,
+                estimated_time: route.estimated_time,
+                estimated_gas_cost: gas_cost,
+                bridge_fee: route.fee,
+                bridge_fee_usd: fee_usd,
+                protocol_fee,
+                protocol_fee_usd,
+                integrator_fee,
+                integrator_fee_usd,
+                native_drop_usd,
+                total_cost_usd: total_cost,
+                amount_out: bridged_amount.saturating_sub(route.fee),
+                available,
+            }));
+        }
+
+        routes.sort_by_key(|(score, route)| (!route.available, *score));
+        Ok(routes
+            .into_iter()
+            .take(self.max_routes.saturating_to())
+            .map(|(_, route)| route)
+            .collect())
+    }
+
+    /// Split `request.amount` across up to `max_split_legs` adapters, giving each
+    /// `split_increment_bps` slice to the adapter whose amount out grows the most.
+    /// Returns (adapter, amount, amount_out) legs.
+    pub fn get_split_quote(&self, request: &TransferRequest) -> Result<Vec<(Address, U256, U256)>, SettlementError> {
+        self.ensure_not_blocked(&[msg::sender(), request.recipient])?;
+        ensure!(
+            self.supported_tokens.get(&request.token).unwrap_or(&false),
+            TokenNotSupported { token: request.token }
+        );
+        self.ensure_integrator(request)?;
+
+        let slices = 10_000 / self.split_increment_bps.to::<u64>();
+        let slice = request.amount * self.split_increment_bps / U256::from(10_000);
+        let max_legs: usize = self.max_split_legs.saturating_to();
+
+        let mut legs: Vec<(Address, U256, U256)> = (0..self.bridge_adapters.len())
+            .map(|i| (self.bridge_adapters.get(i).unwrap(), U256::ZERO, U256::ZERO))
+            .collect();
+        let mut allocated = U256::ZERO;
+
+        for step in 0..slices {
+            // The last slice carries the rounding remainder
+            let chunk = if step + 1 == slices { request.amount - allocated } else { slice };
+            let used = legs.iter().filter(|leg| leg.1 > U256::ZERO).count();
+
+            let mut best: Option<(usize, U256, U256)> = None;
+            for (i, (adapter_addr, allocation, amount_out)) in legs.iter().enumerate() {
+                if *allocation == U256::ZERO && used >= max_legs {
+                    continue;
+                }
+                if self.route_paused(*adapter_addr, request) {
+                    continue;
+                }
+                let next = *allocation + chunk;
+                let (protocol_fee, integrator_fee) = self.transfer_fees(request, next);
+                let bridged = next - protocol_fee - integrator_fee;
+                let adapter = self.get_bridge_adapter(*adapter_addr)?;
+                let out = match adapter.get_route(request.from_chain, request.to_chain, request.token, bridged) {
+                    Ok((_, _, _, fee, true)) if fee < bridged => bridged - fee,
+                    _ => continue,
+                };
+                let gain = out.saturating_sub(*amount_out);
+                if best.map_or(true, |(_, best_gain, _)| gain > best_gain) {
+                    best = Some((i, gain, out));
+                }
+            }
+
+            let (i, _, out) = best.ok_or_else(|| -> SettlementError {
+                NoRouteAvailable { toChain: request.to_chain, token: request.token }.into()
+            })?;
+            legs[i].1 += chunk;
+            legs[i].2 = out;
+            allocated += chunk;
+        }
+
+        legs.retain(|leg| leg.1 > U256::ZERO);
+        Ok(legs)
+    }
+
+    /// Execute every leg of the split quote; any failing leg reverts the whole call.
+    /// Split transfers do not take a native drop or payload.
+    pub fn execute_split(&mut self, request: TransferRequest) -> Result<Vec<B256>, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(request.deadline)?;
+            ensure!(
+                request.native_drop_amount == U256::ZERO,
+                NativeDropNotSupported { adapter: Address::ZERO }
+            );
+            ensure!(request.payload.is_empty(), PayloadNotSupported { adapter: Address::ZERO });
+            let legs = this.get_split_quote(&request)?;
+            let total_out = legs.iter().fold(U256::ZERO, |total, leg| total + leg.2);
+            ensure!(
+                total_out >= request.min_amount_out,
+                InsufficientOutput { amountOut: total_out, minAmountOut: request.min_amount_out }
+            );
+
+            let mut transfer_ids = Vec::new();
+            for (adapter, amount, amount_out) in legs {
+                let leg = TransferRequest {
+                    amount,
+                    min_amount_out: amount_out * request.min_amount_out / total_out,
+                    ..request.clone()
+                };
+                transfer_ids.push(this.dispatch(adapter, &leg)?);
+            }
+            Ok(transfer_ids)
+        })
+    }
+
+    /// Route many transfers in one call, pulling each token's total once. With
+    /// `skip_failed`, failing legs are refunded to the sender and get a zero id;
+    /// otherwise any failure reverts the whole batch.
+    pub fn execute_batch(
+        &mut self,
+        requests: Vec<TransferRequest>,
+        skip_failed: bool,
+    ) -> Result<Vec<B256>, SettlementError> {
+        self.non_reentrant(|this| {
+            let mut totals: Vec<(Address, U256)> = Vec::new();
+            for request in &requests {
+                ensure!(
+                    this.supported_tokens.get(&request.token).unwrap_or(&false),
+                    TokenNotSupported { token: request.token }
+                );
+                match totals.iter_mut().find(|(token, _)| *token == request.token) {
+                    Some((_, total)) => *total += request.amount,
+                    None => totals.push((request.token, request.amount)),
+                }
+            }
+            for (token, total) in totals {
+                ERC20Helper::transfer_from(token, msg::sender(), contract::address(), total)?;
+            }
+
+            let mut transfer_ids = Vec::new();
+            for (index, request) in requests.into_iter().enumerate() {
+                let result = this.ensure_deadline(request.deadline)
+                    .and_then(|_| this.find_best_route(request.clone()))
+                    .and_then(|route| {
+                        ensure!(
+                            route.amount_out >= request.min_amount_out,
+                            InsufficientOutput { amountOut: route.amount_out, minAmountOut: request.min_amount_out }
+                        );
+                        this.dispatch(route.bridge_adapter, &request)
+                    });
+
+                match result {
+                    Ok(transfer_id) => transfer_ids.push(transfer_id),
+                    Err(err) => {
+                        ensure!(skip_failed, err);
+                        ERC20Helper::transfer(request.token, msg::sender(), request.amount)?;
+                        evm::log(BatchLegRefunded {
+                            index: U256::from(index),
+                            sender: msg::sender(),
+                            token: request.token,
+                            amount: request.amount,
+                            recipient: request.recipient,
+                        });
+                        transfer_ids.push(B256::ZERO);
+                    }
+                }
+            }
+
+            Ok(transfer_ids)
+        })
+    }
+
+    pub fn execute_transfer(&mut self, request: TransferRequest) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(request.deadline)?;
+            let route = this.find_best_route(request.clone())?;
+            ensure!(
+                route.available,
+                NoRouteAvailable { toChain: request.to_chain, token: request.token }
+            );
+            ensure!(
+                route.amount_out >= request.min_amount_out,
+                InsufficientOutput { amountOut: route.amount_out, minAmountOut: request.min_amount_out }
+            );
+
+            this.dispatch(route.bridge_adapter, &request)?;
+            Ok(())
+        })
+    }
+
+    /// Execute through a route the user picked from `get_routes`, re-quoting the
+    /// adapter so the result is no worse than `request.min_amount_out`
+    pub fn execute_with_adapter(
+        &mut self,
+        adapter_addr: Address,
+        request: TransferRequest,
+    ) -> Result<B256, SettlementError> {
+        self.non_reentrant(|this| {
+            this.ensure_deadline(request.deadline)?;
+            ensure!(
+                this.supported_tokens.get(&request.token).unwrap_or(&false),
+                TokenNotSupported { token: request.token }
+            );
+            this.ensure_integrator(&request)?;
+
+            let adapter = this.get_bridge_adapter(adapter_addr)?;
+            ensure!(
+                request.native_drop_amount == U256::ZERO || adapter.supports_native_drop()?,
+                NativeDropNotSupported { adapter: adapter_addr }
+            );
+            ensure!(
+                request.payload.is_empty() || adapter.supports_payload()?,
+                PayloadNotSupported { adapter: adapter_addr }
+            );
+            let (protocol_fee, integrator_fee) = this.transfer_fees(&request, request.amount);
+            let bridged_amount = request.amount - protocol_fee - integrator_fee;
+            let (_, _, _, fee, available) = adapter.get_route(
+                request.from_chain,
                 request.to_chain,
-                route.estimated_gas,
+                request.token,
+                bridged_amount,
             )?;
+            ensure!(
+                available,
+                NoRouteAvailable { toChain: request.to_chain, token: request.token }
+            );
+
+            let amount_out = bridged_amount.saturating_sub(fee);
+            ensure!(
+                amount_out >= request.min_amount_out,
+                InsufficientOutput { amountOut: amount_out, minAmountOut: request.min_amount_out }
+            );
+
+            this.dispatch(adapter_addr, &request)
+        })
+    }
+
+    /// Credit a refund a bridge pushed back to the router to `transfer_id`
+    pub fn process_refund(&mut self, transfer_id: B256, token: Address, amount: U256) -> Result<(), SettlementError> {
+        self.non_reentrant(|this| {
+            this.ownable.only_owner()?;
+            ensure!(
+                *this.transfer_senders.get(&transfer_id).unwrap_or(&Address::ZERO) != Address::ZERO,
+                TransferNotFound { transferId: transfer_id }
+            );
+            let pending = *this.refund_amounts.get(&transfer_id).unwrap_or(&U256::ZERO);
+            let pending_token = *this.refund_tokens.get(&transfer_id).unwrap_or(&token);
+            ensure!(
+                pending == U256::ZERO || pending_token == token,
+                RefundTokenMismatch { pendingToken: pending_token, token }
+            );
+
+            // Only credit funds the router actually holds beyond earlier refunds and fees
+            let liabilities = *this.refund_liabilities.get(&token).unwrap_or(&U256::ZERO) + amount;
+            let required = liabilities
+                + *this.accrued_fees.get(&token).unwrap_or(&U256::ZERO)
+                + *this.integrator_fee_liabilities.get(&token).unwrap_or(&U256::ZERO);
+            let balance = ERC20Helper::balance_of(token, contract::address());
+            ensure!(balance >= required, InsufficientRefundBalance { token, balance, required });
+
+            this.refund_liabilities.insert(token, liabilities);
+            this.refund_tokens.insert(transfer_id, token);
+            this.refund_amounts.insert(transfer_id, pending + amount);
+            evm::log(RefundProcessed { transferId: transfer_id, token, amount });
+            Ok(())
+        })
+    }
+
+    /// Claim the refund credited to `transfer_id`; only its original sender can
+    pub fn claim_refund(&mut self, transfer_id: B256) -> Result<U256, SettlementError> {
+        self.non_reentrant(|this| {
+            let sender = msg::sender();
+            this.ensure_not_blocked(&[sender])?;
+            ensure!(
+                *this.transfer_senders.get(&transfer_id).unwrap_or(&Address::ZERO) == sender,
+                NotTransferSender { transferId: transfer_id, caller: sender }
+            );
+            let amount = *this.refund_amounts.get(&transfer_id).unwrap_or(&U256::ZERO);
+            ensure!(amount > U256::ZERO, NoRefundAvailable { transferId: transfer_id });
+
+            let token = *this.refund_tokens.get(&transfer_id).unwrap();
+            this.refund_amounts.insert(transfer_id, U256::ZERO);
+            let liabilities = *this.refund_liabilities.get(&token).unwrap_or(&U256::ZERO);
+            this.refund_liabilities.insert(token, liabilities - amount);
+            ERC20Helper::transfer(token, sender, amount)?;
+
+            evm::log(RefundClaimed { transferId: transfer_id, sender, token, amount });
+            Ok(amount)
+        })
+    }
+
+    // Helper functions
+    /// The owner passes every role check
+    fn ensure_role(&self, role: B256) -> Result<(), SettlementError> {
+        if self.ownable.only_owner().is_ok() {
+            return Ok(());
+        }
+        self.access_control.only_role(role)
+    }
+
+    fn apply_price_oracle(&mut self, price_oracle: Address) -> Result<(), SettlementError> {
+        ensure!(price_oracle != Address::ZERO, InvalidOracle {});
+
+        let previous = self.price_oracle;
+        self.price_oracle = price_oracle;
+
+        evm::log(PriceOracleUpdated { previousOracle: previous, newOracle: price_oracle });
+        Ok(())
+    }
+
+    fn apply_bridge_adapter(&mut self, adapter: Address) -> Result<(), SettlementError> {
+        ensure!(adapter != Address::ZERO, InvalidAdapter { adapter });
+        ensure!(!self.is_bridge_adapter.get(&adapter).unwrap_or(&false), AdapterExists { adapter });
+
+        // Verify the adapter implements IBridgeAdapter
+        let bridge = self.get_bridge_adapter(adapter)?;
+        let (name, supported) = bridge.get_bridge_info()?;
+        ensure!(supported, BridgeNotSupported { adapter });
+
+        self.bridge_adapters.push(adapter);
+        self.is_bridge_adapter.insert(adapter, true);
+
+        evm::log(BridgeAdapterAdded { adapter });
+        Ok(())
+    }
+
+    fn apply_protocol_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        let max_fee_bps = U256::from(MAX_PROTOCOL_FEE_BPS);
+        ensure!(fee_bps <= max_fee_bps, FeeTooHigh { feeBps: fee_bps, maxFeeBps: max_fee_bps });
+
+        let previous = self.protocol_fee_bps;
+        self.protocol_fee_bps = fee_bps;
+
+        evm::log(ProtocolFeeUpdated { previousFeeBps: previous, newFeeBps: fee_bps });
+        Ok(())
+    }
+
+    fn apply_fee_recipient(&mut self, recipient: Address) -> Result<(), SettlementError> {
+        ensure!(recipient != Address::ZERO, InvalidAddress {});
+
+        let previous = self.fee_recipient;
+        self.fee_recipient = recipient;
+
+        evm::log(FeeRecipientUpdated { previousRecipient: previous, newRecipient: recipient });
+        Ok(())
+    }
+
+    fn apply_max_integrator_fee(&mut self, fee_bps: U256) -> Result<(), SettlementError> {
+        let ceiling = U256::from(MAX_INTEGRATOR_FEE_BPS);
+        ensure!(fee_bps <= ceiling, FeeTooHigh { feeBps: fee_bps, maxFeeBps: ceiling });
+
+        let previous = self.max_integrator_fee_bps;
+        self.max_integrator_fee_bps = fee_bps;
+
+        evm::log(MaxIntegratorFeeUpdated { previousFeeBps: previous, newFeeBps: fee_bps });
+        Ok(())
+    }
 
-            let total_cost = gas_cost; // In a full implementation, would add bridge fee converted to USD
-
-            if total_cost < best_cost {
-                best_cost = total_cost;
-                best_route = Some(RouteInfo {
-                    bridge_adapter: adapter_addr,
-                    bridge_name: route.bridge_name,
-                    estimated_time: route.estimated_time,
-                    estimated_gas_cost: gas_cost,
-                    bridge_fee: route.fee,
-                    total_cost_usd: total_cost,
-                    amount_out: request.amount.saturating_sub(route.fee),
-                    available: true,
-                });
+    fn apply_timelock_delay(&mut self, delay: U256) -> Result<(), SettlementError> {
+        let max_delay = U256::from(MAX_TIMELOCK_DELAY);
+        ensure!(delay <= max_delay, InvalidTimelockDelay { delay, maxDelay: max_delay });
+
+        let previous = self.timelock_delay;
+        self.timelock_delay = delay;
+
+        evm::log(TimelockDelayUpdated { previousDelay: previous, newDelay: delay });
+        Ok(())
+    }
+
+    fn apply_screening_oracle(&mut self, oracle: Address) -> Result<(), SettlementError> {
+        let previous = self.screening_oracle;
+        self.screening_oracle = oracle;
+        evm::log(ScreeningOracleUpdated { previousOracle: previous, newOracle: oracle });
+        Ok(())
+    }
+
+    /// Reject denylisted or sanctioned `accounts`; a reverting oracle blocks everyone
+    fn ensure_not_blocked(&self, accounts: &[Address]) -> Result<(), SettlementError> {
+        for &account in accounts {
+            ensure!(!self.denylist.get(&account).unwrap_or(&false), AddressBlocked { account });
+            if self.screening_oracle != Address::ZERO {
+                let oracle = self.screening_oracle;
+                let sanctioned = ISanctionsList::new(oracle)
+                    .is_sanctioned(account)
+                    .map_err(|_| ScreeningFailed { oracle, account })?;
+                ensure!(!sanctioned, AddressBlocked { account });
             }
         }
+        Ok(())
+    }
 
-        best_route.ok_or_else(|| "No route available".as_bytes().to_vec())
+    fn ensure_timelock_off(&self) -> Result<(), SettlementError> {
+        ensure!(self.timelock_delay == U256::ZERO, TimelockRequired {});
+        Ok(())
     }
 
-    pub fn execute_transfer(&mut self, request: TransferRequest) -> Result<(), Vec<u8>> {
-        let route = self.find_best_route(request.clone())?;
-        ensure!(route.available, "Route not available");
+    /// Operations need the same role as their direct setter; the rest the owner
+    fn ensure_operation_auth(&self, kind: u8) -> Result<(), SettlementError> {
+        match kind {
+            OP_ADD_BRIDGE_ADAPTER => self.ensure_role(ADAPTER_MANAGER_ROLE),
+            OP_SET_PROTOCOL_FEE | OP_SET_MAX_INTEGRATOR_FEE => self.ensure_role(FEE_MANAGER_ROLE),
+            OP_SET_PRICE_ORACLE | OP_SET_FEE_RECIPIENT | OP_TRANSFER_OWNERSHIP
+            | OP_RENOUNCE_OWNERSHIP | OP_SET_TIMELOCK_DELAY | OP_SET_SCREENING_ORACLE => {
+                self.ownable.only_owner()
+            }
+            _ => Err(InvalidOperation { kind }.into()),
+        }
+    }
 
-        let adapter = self.get_bridge_adapter(route.bridge_adapter)?;
-        
-        // Execute the bridge transaction
-        adapter.bridge_tokens(
+    fn apply_operation(&mut self, kind: u8, value: U256) -> Result<(), SettlementError> {
+        let address = Address::from_word(value.into());
+        match kind {
+            OP_ADD_BRIDGE_ADAPTER => self.apply_bridge_adapter(address),
+            OP_SET_PRICE_ORACLE => self.apply_price_oracle(address),
+            OP_SET_PROTOCOL_FEE => self.apply_protocol_fee(value),
+            OP_SET_FEE_RECIPIENT => self.apply_fee_recipient(address),
+            OP_SET_MAX_INTEGRATOR_FEE => self.apply_max_integrator_fee(value),
+            OP_TRANSFER_OWNERSHIP => self.ownable.transfer_ownership(address),
+            OP_RENOUNCE_OWNERSHIP => self.ownable.renounce_ownership(),
+            OP_SET_TIMELOCK_DELAY => self.apply_timelock_delay(value),
+            OP_SET_SCREENING_ORACLE => self.apply_screening_oracle(address),
+            _ => Err(InvalidOperation { kind }.into()),
+        }
+    }
+
+    /// Pausing needs the pauser role, unpausing the owner
+    fn ensure_pause_change(&self, paused: bool) -> Result<(), SettlementError> {
+        if paused {
+            self.ensure_role(PAUSER_ROLE)
+        } else {
+            self.ownable.only_owner()
+        }
+    }
+
+    fn ensure_not_paused(&self, adapter_addr: Address, request: &TransferRequest) -> Result<(), SettlementError> {
+        ensure!(!self.paused, RoutingPaused {});
+        ensure!(
+            !self.paused_chains.get(&request.to_chain).unwrap_or(&false),
+            ChainPaused { chainId: request.to_chain }
+        );
+        ensure!(
+            !self.paused_tokens.get(&request.token).unwrap_or(&false),
+            TokenPaused { token: request.token }
+        );
+        ensure!(
+            !self.paused_adapters.get(&adapter_addr).unwrap_or(&false),
+            AdapterPaused { adapter: adapter_addr }
+        );
+        Ok(())
+    }
+
+    fn route_paused(&self, adapter_addr: Address, request: &TransferRequest) -> bool {
+        self.ensure_not_paused(adapter_addr, request).is_err()
+    }
+
+    /// USD volume `adapter` can still take under its exposure cap
+    fn exposure_remaining(&self, adapter: Address) -> U256 {
+        match *self.adapter_exposure_caps.get(&adapter).unwrap_or(&U256::ZERO) {
+            cap if cap == U256::ZERO => U256::MAX,
+            cap => cap.saturating_sub(*self.adapter_exposure.get(&adapter).unwrap_or(&U256::ZERO)),
+        }
+    }
+
+    /// Failures in `adapter`'s current run; zero once a day passes without one
+    fn recent_failures(&self, adapter: Address) -> U256 {
+        let last = *self.adapter_last_failure.get(&adapter).unwrap_or(&U256::ZERO);
+        if U256::from(block::timestamp()).saturating_sub(last) > U256::from(DAY) {
+            return U256::ZERO;
+        }
+        *self.adapter_recent_failures.get(&adapter).unwrap_or(&U256::ZERO)
+    }
+
+    /// Reject sending `amount` of `token` beyond any rate limit for the caller
+    fn ensure_within_limits(&self, token: Address, amount: U256) -> Result<(), SettlementError> {
+        let user = msg::sender();
+        let (max_transfer, hourly, daily, user_daily) = self.get_rate_limit_capacity(token, user);
+        ensure!(amount <= max_transfer, TransferLimitExceeded { token, amount, maxAmount: max_transfer });
+        for (window, remaining) in [(HOUR, hourly), (DAY, daily)] {
+            ensure!(
+                amount <= remaining,
+                RateLimitExceeded { token, window: U256::from(window), amount, remaining }
+            );
+        }
+        ensure!(amount <= user_daily, UserRateLimitExceeded { user, token, amount, remaining: user_daily });
+        Ok(())
+    }
+
+    fn ensure_deadline(&self, deadline: U256) -> Result<(), SettlementError> {
+        let now = U256::from(block::timestamp());
+        ensure!(now <= deadline, DeadlineExpired { deadline, timestamp: now });
+        Ok(())
+    }
+
+    fn ensure_integrator(&self, request: &TransferRequest) -> Result<(), SettlementError> {
+        ensure!(
+            request.integrator_fee_bps <= self.max_integrator_fee_bps,
+            FeeTooHigh { feeBps: request.integrator_fee_bps, maxFeeBps: self.max_integrator_fee_bps }
+        );
+        ensure!(
+            request.integrator_fee_bps == U256::ZERO || request.integrator != Address::ZERO,
+            InvalidAddress {}
+        );
+        Ok(())
+    }
+
+    /// Protocol and integrator fees on `amount` of `request`, rounded down
+    fn transfer_fees(&self, request: &TransferRequest, amount: U256) -> (U256, U256) {
+        (
+            amount * self.protocol_fee_bps / U256::from(10_000),
+            amount * request.integrator_fee_bps / U256::from(10_000),
+        )
+    }
+
+    /// Bridge `request.amount` less the protocol and integrator fees, which accrue
+    /// to the protocol and the integrator
+    fn dispatch(&mut self, adapter_addr: Address, request: &TransferRequest) -> Result<B256, SettlementError> {
+        self.ensure_not_paused(adapter_addr, request)?;
+        self.ensure_not_blocked(&[msg::sender(), request.recipient])?;
+        self.ensure_within_limits(request.token, request.amount)?;
+        let adapter = self.get_bridge_adapter(adapter_addr)?;
+        // Native value is only forwarded to adapters that bridge it directly
+        ensure!(
+            request.token != NATIVE_TOKEN || adapter.supports_native()?,
+            BridgeNotSupported { adapter: adapter_addr }
+        );
+
+        let (protocol_fee, integrator_fee) = self.transfer_fees(request, request.amount);
+        let amount = request.amount - protocol_fee - integrator_fee;
+        let volume_usd = self.price_oracle()?.get_token_price(request.token)?
+            .saturating_mul(request.amount)
+            .saturating_div(U256::from(10).pow(U256::from(18)));
+        let remaining_usd = self.exposure_remaining(adapter_addr);
+        ensure!(
+            volume_usd <= remaining_usd,
+            ExposureCapExceeded { adapter: adapter_addr, volumeUsd: volume_usd, remainingUsd: remaining_usd }
+        );
+
+        // Book fees and statistics before calling the adapter
+        let transfer_id = self.transfer_id(self.total_transfers);
+        self.book_transfer(transfer_id, adapter_addr, request, protocol_fee, integrator_fee, volume_usd, true);
+
+        let bridged = adapter.bridge_tokens(
+            transfer_id,
             request.to_chain,
             request.token,
-            request.amount,
+            amount,
             request.recipient,
-            vec![], // Additional data if needed
-        )?;
+            request.min_amount_out,
+            request.deadline,
+            request.native_drop_amount,
+            request.payload.clone(),
+        );
+        if let Err(err) = bridged {
+            // Batches recover from failed legs, so undo the booking
+            self.book_transfer(transfer_id, adapter_addr, request, protocol_fee, integrator_fee, volume_usd, false);
+            return Err(err.into());
+        }
 
-        // Update statistics
-        self.total_transfers += U256::from(1);
-        self.total_volume_usd += self.price_oracle()?.get_token_price(request.token)?
-            .saturating_mul(request.amount)
-            .saturating_div(U256::from(10).pow(U256::from(18)));
+        evm::log(CrossChainTransferInitiated {
+            transferId: transfer_id,
+            sender: msg::sender(),
+            recipient: request.recipient,
+            bridgeAdapter: adapter_addr,
+            token: request.token,
+            amount,
+            toChain: request.to_chain,
+            integrator: request.integrator,
+        });
 
-        Ok(())
+        Ok(transfer_id)
     }
 
-    // Helper functions
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        ensure!(msg::sender() == self.owner, "Not owner");
-        Ok(())
+    /// Accrue fees, count the transfer against the stats, rate limits and adapter
+    /// exposure and record it as pending, or with `book` unset reverse all of that
+    #[allow(clippy::too_many_arguments)]
+    fn book_transfer(
+        &mut self,
+        transfer_id: B256,
+        adapter_addr: Address,
+        request: &TransferRequest,
+        protocol_fee: U256,
+        integrator_fee: U256,
+        volume_usd: U256,
+        book: bool,
+    ) {
+        let apply = |value: U256, delta: U256| if book { value + delta } else { value - delta };
+        let accrued = *self.accrued_fees.get(&request.token).unwrap_or(&U256::ZERO);
+        self.accrued_fees.insert(request.token, apply(accrued, protocol_fee));
+        self.total_transfers = apply(self.total_transfers, U256::from(1));
+        self.total_volume_usd = apply(self.total_volume_usd, volume_usd);
+        let sender = if book { msg::sender() } else { Address::ZERO };
+        self.transfer_senders.insert(transfer_id, sender);
+        let exposure = *self.adapter_exposure.get(&adapter_addr).unwrap_or(&U256::ZERO);
+        self.adapter_exposure.insert(adapter_addr, apply(exposure, volume_usd));
+        if book {
+            self.transfer_adapters.insert(transfer_id, adapter_addr);
+            self.transfer_volumes_usd.insert(transfer_id, volume_usd);
+            self.transfer_statuses.insert(transfer_id, TRANSFER_PENDING);
+        } else {
+            self.transfer_adapters.remove(&transfer_id);
+            self.transfer_volumes_usd.remove(&transfer_id);
+            self.transfer_statuses.remove(&transfer_id);
+        }
+
+        let (token, user, now) = (request.token, msg::sender(), U256::from(block::timestamp()));
+        let hourly_limit = *self.hourly_limits.get(&token).unwrap_or(&U256::ZERO);
+        let daily_limit = *self.daily_limits.get(&token).unwrap_or(&U256::ZERO);
+        let user_limit = *self.user_daily_limits.get(&token).unwrap_or(&U256::ZERO);
+        if book {
+            self.hourly_usage.setter(token).consume(request.amount, hourly_limit, HOUR, now);
+            self.daily_usage.setter(token).consume(request.amount, daily_limit, DAY, now);
+            self.user_daily_usage.setter((token, user)).consume(request.amount, user_limit, DAY, now);
+        } else {
+            self.hourly_usage.setter(token).release(request.amount);
+            self.daily_usage.setter(token).release(request.amount);
+            self.user_daily_usage.setter((token, user)).release(request.amount);
+        }
+
+        if request.integrator != Address::ZERO {
+            let key = (request.integrator, request.token);
+            let fees = *self.integrator_fees.get(&key).unwrap_or(&U256::ZERO);
+            self.integrator_fees.insert(key, apply(fees, integrator_fee));
+            let liabilities = *self.integrator_fee_liabilities.get(&request.token).unwrap_or(&U256::ZERO);
+            self.integrator_fee_liabilities.insert(request.token, apply(liabilities, integrator_fee));
+            let transfers = *self.integrator_transfers.get(&request.integrator).unwrap_or(&U256::ZERO);
+            self.integrator_transfers.insert(request.integrator, apply(transfers, U256::from(1)));
+            let volume = *self.integrator_volume_usd.get(&request.integrator).unwrap_or(&U256::ZERO);
+            self.integrator_volume_usd.insert(request.integrator, apply(volume, volume_usd));
+        }
     }
 
-    fn get_bridge_adapter(&self, addr: Address) -> Result<IBridgeAdapterExternal, Vec<u8>> {
-        ensure!(self.is_bridge_adapter.get(&addr).unwrap_or(&false), "Adapter not found");
+    fn get_bridge_adapter(&self, addr: Address) -> Result<IBridgeAdapterExternal, SettlementError> {
+        ensure!(self.is_bridge_adapter.get(&addr).unwrap_or(&false), AdapterNotFound { adapter: addr });
         Ok(IBridgeAdapterExternal::new(addr))
     }
 
-    fn price_oracle(&self) -> Result<IPriceOracleExternal, Vec<u8>> {
-        ensure!(self.price_oracle != Address::ZERO, "Oracle not set");
+    /// Unique id for the `nonce`-th transfer: keccak256(abi.encode(chainid, this, nonce))
+    fn transfer_id(&self, nonce: U256) -> B256 {
+        keccak((U256::from(block::chainid()), contract::address(), nonce).abi_encode())
+    }
+
+    fn price_oracle(&self) -> Result<IPriceOracleExternal, SettlementError> {
+        ensure!(self.price_oracle != Address::ZERO, OracleNotSet {});
         Ok(IPriceOracleExternal::new(self.price_oracle))
     }
 }
\ No newline at end of file
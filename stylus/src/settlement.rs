@@ -0,0 +1,47 @@
+//! Settlement message carried from the source router to the destination
+//! `SettlementReceiver`: `abi.encode(bytes32 transferId, address recipient,
+//! uint256 amount, bytes payload)`.
+//!
+//! Adapters build it from the arguments of `bridgeTokens` and the receiver
+//! decodes it from every bridge delivery. `amount` is what left the source
+//! chain; the receiver settles what arrived instead, as reported by Stargate
+//! and Across or minted by the CCTP burn relayed for the transfer. A non-empty
+//! `payload` is handed to the recipient's `onSettlement` with the funds.
+
+use alloc::vec::Vec;
+use alloy_sol_types::SolValue;
+use stylus_sdk::alloy_primitives::{Address, Bytes, B256, U256};
+
+use crate::errors::{InvalidSettlementMessage, SettlementError};
+
+/// Encode the message for `transfer_id`, paying `amount` to `recipient` with
+/// `payload`
+pub fn encode_message(
+    transfer_id: B256,
+    recipient: Address,
+    amount: U256,
+    payload: &[u8],
+) -> Vec<u8> {
+    (transfer_id, recipient, amount, Bytes::copy_from_slice(payload)).abi_encode_params()
+}
+
+/// A decoded settlement message
+pub struct SettlementMessage {
+    pub transfer_id: B256,
+    pub recipient: Address,
+    pub amount: U256,
+    pub payload: Vec<u8>,
+}
+
+/// Decode a message
+pub fn decode_message(message: &[u8]) -> Result<SettlementMessage, SettlementError> {
+    let (transfer_id, recipient, amount, payload) =
+        <(B256, Address, U256, Bytes)>::abi_decode_params(message, true)
+            .map_err(|_| InvalidSettlementMessage {})?;
+    Ok(SettlementMessage {
+        transfer_id,
+        recipient,
+        amount,
+        payload: payload.to_vec(),
+    })
+}